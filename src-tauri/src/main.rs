@@ -1,745 +1,496 @@
 use tauri::Manager;
-use std::collections::HashMap;
-use std::fs;
-use std::io::Write;
-use std::path::Path;
-use std::process::Command;
-use reqwest;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use serde_json::Value;
-use zip::ZipArchive;
-use std::io::{Read, Cursor};
-use dirs;
 use whoami;
-use std::os::windows::process::CommandExt;
 
-#[derive(serde::Serialize)]
-struct VersionInfo {
-    hash: String,
-    description: String,
+use tfy_core::apps::{self, DownloadRequest};
+use tfy_core::bisect::{self, BisectStatus};
+use tfy_core::catalog::{self, CatalogApp};
+use tfy_core::config::{self, AppConfig};
+use tfy_core::dedupe::DedupeReport;
+use tfy_core::detect::{self, AppStatus};
+use tfy_core::downloads::{self, DownloadManager, DownloadRecord};
+use tfy_core::fflags::{self, FlagSet};
+use tfy_core::installer::{self, find_install, PlayerRequest};
+use tfy_core::launcher::{self, LaunchInfo, Launches};
+use tfy_core::pe::{self, PeInfo};
+use tfy_core::profiles::{self, InstallProfile, ProfileReport};
+use tfy_core::registry::{self, InstallRecord};
+use tfy_core::repair::RepairReport;
+use tfy_core::setup::{self, Confirmed, InstallResult};
+use tfy_core::sysinfo::{self, SystemInfo};
+use tfy_core::tools;
+use tfy_core::versions::{self, VersionInfo, DEFAULT_BINARY_TYPE, DEFAULT_CHANNEL};
+use tfy_core::{AppError, SharedClient};
+
+// Forwards library progress lines to the frontend
+fn progress_emitter(app_handle: &tauri::AppHandle) -> impl Fn(&str) + Send + Sync + '_ {
+    move |message: &str| {
+        let _ = app_handle.emit_all("roblox-progress", message);
+    }
 }
 
-#[derive(serde::Serialize)]
-struct SystemInfo {
-    os: String,
-    cpu: String,
-    ram: String,
-    gpu: String,
+#[tauri::command]
+async fn get_system_info() -> Result<SystemInfo, AppError> {
+    Ok(sysinfo::system_info())
 }
 
 #[tauri::command]
-async fn get_system_info() -> Result<SystemInfo, String> {
-    let mut system_info = SystemInfo {
-        os: "Windows 11".to_string(),
-        cpu: "Unknown Processor".to_string(),
-        ram: "Memory information unavailable".to_string(),
-        gpu: "Graphics information unavailable".to_string(),
-    };
+async fn download_player(
+    app_handle: tauri::AppHandle,
+    client: tauri::State<'_, SharedClient>,
+    version_hash: String,
+    channel: Option<String>,
+    binary_type: Option<String>,
+    package_dir: Option<String>,
+) -> Result<String, AppError> {
+    let mut request = PlayerRequest::new(
+        version_hash,
+        channel.unwrap_or_else(|| DEFAULT_CHANNEL.to_string()),
+        binary_type.unwrap_or_else(|| DEFAULT_BINARY_TYPE.to_string()),
+    );
+    request.package_dir = package_dir.map(PathBuf::from);
+
+    let record = installer::download_player(client.inner().as_ref(), &progress_emitter(&app_handle), &request).await?;
+    Ok(format!("✅ Roblox {} downloaded successfully to {}", record.binary_type, record.path.display()))
+}
 
-    // Get OS information
-    match Command::new("cmd")
-        .args(&["/C", "ver"])
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .output()
-    {
-        Ok(output) => {
-            let os_output = String::from_utf8_lossy(&output.stdout);
-            if os_output.contains("10.0") {
-                if os_output.contains("22000") || os_output.contains("22621") || os_output.contains("22631") {
-                    system_info.os = "Windows 11".to_string();
-                } else {
-                    system_info.os = "Windows 10".to_string();
-                }
-            }
-        }
-        Err(_) => {}
-    }
+#[tauri::command]
+async fn verify_installed_version(
+    version_hash: String,
+    binary_type: Option<String>,
+) -> Result<BTreeMap<String, Vec<String>>, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    installer::verify_install(&record)
+}
 
-    // Get CPU information
-    match Command::new("wmic")
-        .args(&["cpu", "get", "name", "/format:value"])
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .output()
-    {
-        Ok(output) => {
-            let cpu_output = String::from_utf8_lossy(&output.stdout);
-            for line in cpu_output.lines() {
-                if line.starts_with("Name=") && line.len() > 5 {
-                    let cpu_name = line[5..].trim();
-                    if !cpu_name.is_empty() {
-                        system_info.cpu = cpu_name.to_string();
-                        break;
-                    }
-                }
-            }
-        }
-        Err(_) => {}
-    }
+#[tauri::command]
+async fn repair_installed_version(
+    app_handle: tauri::AppHandle,
+    client: tauri::State<'_, SharedClient>,
+    version_hash: String,
+    binary_type: Option<String>,
+) -> Result<RepairReport, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    installer::repair_install(client.inner().as_ref(), &progress_emitter(&app_handle), &record).await
+}
 
-    // Get RAM information
-    match Command::new("wmic")
-        .args(&["computersystem", "get", "TotalPhysicalMemory", "/format:value"])
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .output()
-    {
-        Ok(output) => {
-            let ram_output = String::from_utf8_lossy(&output.stdout);
-            for line in ram_output.lines() {
-                if line.starts_with("TotalPhysicalMemory=") {
-                    if let Ok(bytes) = line[20..].trim().parse::<u64>() {
-                        let gb = bytes / (1024 * 1024 * 1024);
-                        system_info.ram = format!("{} GB RAM", gb);
-                        break;
-                    }
-                }
-            }
-        }
-        Err(_) => {}
-    }
+#[tauri::command]
+async fn dedupe_installs(dry_run: Option<bool>) -> Result<DedupeReport, AppError> {
+    installer::dedupe_installs(dry_run.unwrap_or(false)).await
+}
 
-    // Get GPU information
-    match Command::new("wmic")
-        .args(&["path", "win32_VideoController", "get", "name", "/format:value"])
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .output()
-    {
-        Ok(output) => {
-            let gpu_output = String::from_utf8_lossy(&output.stdout);
-            for line in gpu_output.lines() {
-                if line.starts_with("Name=") && line.len() > 5 {
-                    let gpu_name = line[5..].trim();
-                    if !gpu_name.is_empty() && !gpu_name.contains("Microsoft") {
-                        system_info.gpu = gpu_name.to_string();
-                        break;
-                    }
-                }
-            }
-        }
-        Err(_) => {}
-    }
+#[tauri::command]
+async fn get_fflags(version_hash: String, binary_type: Option<String>) -> Result<FlagSet, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    fflags::read_flags(&record.path)
+}
+
+#[tauri::command]
+async fn set_fflag(
+    version_hash: String,
+    binary_type: Option<String>,
+    name: String,
+    value: Value,
+) -> Result<FlagSet, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    fflags::set_flag(&record.path, name, value)
+}
 
-    Ok(system_info)
+#[tauri::command]
+async fn remove_fflag(version_hash: String, binary_type: Option<String>, name: String) -> Result<FlagSet, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    fflags::remove_flag(&record.path, &name)
 }
 
 #[tauri::command]
-async fn download_player(
+async fn list_fflag_presets() -> Result<BTreeMap<String, FlagSet>, AppError> {
+    fflags::load_presets()
+}
+
+#[tauri::command]
+async fn save_fflag_preset(name: String, flags: FlagSet) -> Result<(), AppError> {
+    fflags::save_preset(name, flags)
+}
+
+#[tauri::command]
+async fn delete_fflag_preset(name: String) -> Result<(), AppError> {
+    fflags::delete_preset(&name)
+}
+
+#[tauri::command]
+async fn apply_fflag_preset(
+    version_hash: String,
+    binary_type: Option<String>,
+    preset: String,
+    replace: Option<bool>,
+) -> Result<FlagSet, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    fflags::apply_preset(&record.path, &preset, replace.unwrap_or(false))
+}
+
+#[tauri::command]
+async fn import_fflags(
+    version_hash: String,
+    binary_type: Option<String>,
+    path: String,
+    replace: Option<bool>,
+) -> Result<FlagSet, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    fflags::import_file(&record.path, Path::new(&path), replace.unwrap_or(false))
+}
+
+#[tauri::command]
+async fn export_fflags(version_hash: String, binary_type: Option<String>, path: String) -> Result<String, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    let count = fflags::export_file(&record.path, Path::new(&path))?;
+    Ok(format!("✅ Exported {} flags to {}", count, path))
+}
+
+#[tauri::command]
+async fn launch_installed_version(
     app_handle: tauri::AppHandle,
+    launches: tauri::State<'_, Launches>,
     version_hash: String,
+    binary_type: Option<String>,
+    args: Option<Vec<String>>,
+) -> Result<LaunchInfo, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    let app_config = config::load()?;
+
+    let child = launcher::spawn(&record, &args.unwrap_or_default(), app_config.launch.wrapper.as_deref())?;
+    let info = launches.track(&record, child, move |exit| {
+        let _ = app_handle.emit_all("launch-exited", exit);
+    });
+
+    Ok(info)
+}
+
+#[tauri::command]
+async fn get_running_versions(launches: tauri::State<'_, Launches>) -> Result<Vec<LaunchInfo>, AppError> {
+    Ok(launches.list())
+}
+
+#[tauri::command]
+async fn bisect_start(
+    client: tauri::State<'_, SharedClient>,
+    good_hash: String,
+    bad_hash: String,
     channel: Option<String>,
     binary_type: Option<String>,
-) -> Result<String, String> {
-    let channel = channel.unwrap_or_else(|| "LIVE".to_string());
-    let binary_type = binary_type.unwrap_or_else(|| "WindowsPlayer".to_string());
-    
-    // Emit progress updates to the frontend
-    let emit_progress = |message: &str| {
-        let _ = app_handle.emit_all("roblox-progress", message);
-    };
+) -> Result<BisectStatus, AppError> {
+    let channel = channel.unwrap_or_else(|| DEFAULT_CHANNEL.to_string());
+    let binary_type = binary_type.unwrap_or_else(|| DEFAULT_BINARY_TYPE.to_string());
 
-    emit_progress("▶ Starting download…");
+    let session = bisect::begin(client.inner().as_ref(), &good_hash, &bad_hash, &channel, &binary_type).await?;
+    Ok(session.status())
+}
 
-    // Normalize version hash
-    let version_norm = if version_hash.to_lowercase().starts_with("version-") {
-        version_hash.to_lowercase()
-    } else {
-        format!("version-{}", version_hash.to_lowercase())
-    };
+#[tauri::command]
+async fn bisect_status() -> Result<Option<BisectStatus>, AppError> {
+    Ok(bisect::load()?.map(|session| session.status()))
+}
+
+#[tauri::command]
+async fn bisect_install_current(
+    app_handle: tauri::AppHandle,
+    client: tauri::State<'_, SharedClient>,
+) -> Result<String, AppError> {
+    let session = bisect::load_running()?;
+    let current = session.current()
+        .ok_or_else(|| AppError::validation("Bisect is already finished"))?
+        .to_string();
+
+    download_player(app_handle, client, current, Some(session.channel.clone()), Some(session.binary_type.clone()), None).await
+}
 
-    // Binary type configuration
-    let binary_configs = get_binary_configs();
-    let binary_config = binary_configs.get(&binary_type)
-        .ok_or_else(|| format!("Unknown binary type: {}", binary_type))?;
+#[tauri::command]
+async fn bisect_mark(verdict: String) -> Result<BisectStatus, AppError> {
+    bisect::record_verdict(&verdict)
+}
+
+#[tauri::command]
+async fn bisect_reset() -> Result<(), AppError> {
+    bisect::clear()
+}
 
-    // Build base URL
-    let base_url = if channel.to_uppercase() == "LIVE" {
-        "https://setup.rbxcdn.com".to_string()
-    } else {
-        format!("https://setup.rbxcdn.com/channel/{}", channel.to_lowercase())
+#[tauri::command]
+async fn get_app_config() -> Result<AppConfig, AppError> {
+    config::load()
+}
+
+#[tauri::command]
+async fn save_app_config(app_config: AppConfig) -> Result<(), AppError> {
+    app_config.app_settings.render("WindowsPlayer")?;
+    app_config.app_settings.render("WindowsStudio64")?;
+    app_config.save()
+}
+
+#[tauri::command]
+async fn get_installed_versions() -> Result<Vec<InstallRecord>, AppError> {
+    let registry = registry::load()?;
+    Ok(registry.installs)
+}
+
+#[tauri::command]
+async fn export_version(
+    version_hash: String,
+    binary_type: Option<String>,
+    destination: Option<String>,
+) -> Result<String, AppError> {
+    let record = find_install(&version_hash, binary_type.as_deref())?;
+    let (archive_path, count) = installer::export_version(&record, destination.map(PathBuf::from))?;
+    Ok(format!("✅ Exported {} files of {} to {}", count, record.version, archive_path.display()))
+}
+
+#[tauri::command]
+async fn import_version(archive_path: String) -> Result<String, AppError> {
+    let (record, count) = installer::import_version(Path::new(&archive_path))?;
+    Ok(format!("✅ Imported {} files of {} to {}", count, record.version, record.path.display()))
+}
+
+#[tauri::command]
+async fn get_saved_versions(client: tauri::State<'_, SharedClient>) -> Result<Vec<VersionInfo>, AppError> {
+    versions::fetch_saved_versions(client.inner().as_ref(), versions::SAVED_VERSIONS_URL).await
+}
+
+#[tauri::command]
+async fn list_apps(client: tauri::State<'_, SharedClient>) -> Result<Vec<CatalogApp>, AppError> {
+    // A catalog that fails to download or verify leaves the last good one in place
+    let catalog = match catalog::refresh(client.inner().as_ref()).await {
+        Ok(catalog) => catalog,
+        Err(_) => catalog::load()?,
     };
+    Ok(catalog.apps.into_iter().filter(|app| !app.extra).collect())
+}
+
+// Which catalog apps are already installed, for the badges on the Apps page
+#[tauri::command]
+async fn get_app_statuses() -> Result<Vec<AppStatus>, AppError> {
+    let catalog = catalog::load()?;
+    detect::detect(&catalog.apps, detect::default_detector().as_ref())
+}
 
-    // Fetch manifest
-    let manifest_url = format!("{}{}{}-rbxPkgManifest.txt", base_url, binary_config.blob_dir, version_norm);
-    emit_progress(&format!("⎙ Fetching manifest: {}", manifest_url));
+// Queues the app and resolves once it is saved and verified. A cancelled or
+// failed download rejects with its error.
+#[tauri::command]
+async fn download_app(downloads: tauri::State<'_, DownloadManager>, app_id: String) -> Result<DownloadRecord, AppError> {
+    let catalog = catalog::load()?;
+    let app = catalog.find(&app_id)?;
 
-    let client = reqwest::Client::new();
-    let manifest_response = client.get(&manifest_url)
-        .timeout(std::time::Duration::from_secs(30))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch manifest: {}", e))?;
+    let request = DownloadRequest::for_app(app, &apps::default_download_dir()?);
+    let download = downloads.enqueue(request)?;
+    downloads.wait(download.id).await
+}
 
-    if manifest_response.status() == 403 {
-        return Err("❌ Error: version hash invalid or service unavailable.".to_string());
-    }
+// Runs the installer of an app downloaded through download_app unattended,
+// once the page has shown its details like for run_downloaded_file. Resolves
+// once the installer has exited, a failing one with outcome "failed".
+#[tauri::command]
+async fn install_download(
+    downloads: tauri::State<'_, DownloadManager>,
+    app_id: String,
+    id: u64,
+) -> Result<InstallResult, AppError> {
+    let catalog = catalog::load()?;
+    let app = catalog.find(&app_id)?;
+    setup::install_download(downloads.inner(), app, id).await
+}
 
-    let manifest_text = manifest_response.text().await
-        .map_err(|e| format!("Failed to read manifest: {}", e))?;
+#[tauri::command]
+fn list_profiles() -> Result<Vec<InstallProfile>, AppError> {
+    profiles::list()
+}
 
-    let zip_files: Vec<&str> = manifest_text
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| line.ends_with(".zip"))
-        .collect();
+#[tauri::command]
+fn save_profile(name: String, apps: Vec<String>) -> Result<InstallProfile, AppError> {
+    let profile = InstallProfile::new(&name, apps);
+    profiles::save(&profile, &catalog::load()?)?;
+    Ok(profile)
+}
 
-    if zip_files.is_empty() {
-        return Err("No zip files found in manifest".to_string());
-    }
+#[tauri::command]
+fn delete_profile(name: String) -> Result<(), AppError> {
+    profiles::delete(&name)
+}
+
+#[tauri::command]
+fn import_profile(path: String) -> Result<InstallProfile, AppError> {
+    profiles::import(Path::new(&path), &catalog::load()?)
+}
 
-    // Determine extract roots based on binary type
-    let extract_roots = if binary_type.contains("Player") {
-        get_player_extract_roots()
-    } else {
-        get_studio_extract_roots()
+#[tauri::command]
+fn export_profile(name: String, path: String) -> Result<InstallProfile, AppError> {
+    profiles::export(&name, Path::new(&path))
+}
+
+// Installs every app of the profile in order, reporting each one as a
+// "profile-progress" event, and resolves with the report once all are done
+#[tauri::command]
+async fn run_profile(
+    app_handle: tauri::AppHandle,
+    downloads: tauri::State<'_, DownloadManager>,
+    name: String,
+) -> Result<ProfileReport, AppError> {
+    let profile = profiles::load(&name)?;
+    let catalog = catalog::load()?;
+    let progress = |message: &str| {
+        let _ = app_handle.emit_all("profile-progress", message);
     };
 
-    // Create output directory in Downloads folder
-    let downloads_dir = dirs::download_dir()
-        .ok_or_else(|| "Could not find Downloads directory".to_string())?;
-    let output_dir = downloads_dir.join(&version_norm);
-    fs::create_dir_all(&output_dir)
-        .map_err(|e| format!("Failed to create output directory: {}", e))?;
-
-    emit_progress(&format!("⎙ Created folder: {}", output_dir.display()));
-
-    // Create AppSettings.xml
-    let app_settings_xml = r#"<?xml version="1.0" encoding="UTF-8"?><Settings><ContentFolder>content</ContentFolder><BaseUrl>http://www.roblox.com</BaseUrl></Settings>"#;
-    fs::write(output_dir.join("AppSettings.xml"), app_settings_xml)
-        .map_err(|e| format!("Failed to write AppSettings.xml: {}", e))?;
-
-    // Download and extract each zip file
-    for zip_name in zip_files {
-        let blob_url = format!("{}{}{}-{}", base_url, binary_config.blob_dir, version_norm, zip_name);
-        emit_progress(&format!("↓ Downloading {}", zip_name));
-
-        let zip_response = client.get(&blob_url)
-            .timeout(std::time::Duration::from_secs(60))
-            .send()
-            .await
-            .map_err(|e| format!("Failed to download {}: {}", zip_name, e))?;
-
-        let zip_bytes = zip_response.bytes().await
-            .map_err(|e| format!("Failed to read zip data for {}: {}", zip_name, e))?;
-
-        emit_progress(&format!("⎙ Extracting {}…", zip_name));
-
-        // Extract zip file
-        let cursor = Cursor::new(zip_bytes);
-        let mut archive = ZipArchive::new(cursor)
-            .map_err(|e| format!("Failed to open zip {}: {}", zip_name, e))?;
-
-        // Fix the lifetime issue by using a default empty string
-        let default_root = String::new();
-        let extract_root = extract_roots.get(zip_name).unwrap_or(&default_root);
-        let extract_path = output_dir.join(extract_root);
-
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)
-                .map_err(|e| format!("Failed to read file from {}: {}", zip_name, e))?;
-
-            if file.is_dir() {
-                continue;
-            }
-
-            let file_path = extract_path.join(file.name().replace('\\', "/"));
-            
-            if let Some(parent) = file_path.parent() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
-            }
-
-            let mut output_file = fs::File::create(&file_path)
-                .map_err(|e| format!("Failed to create file {}: {}", file_path.display(), e))?;
-
-            std::io::copy(&mut file, &mut output_file)
-                .map_err(|e| format!("Failed to extract file {}: {}", file_path.display(), e))?;
-        }
-
-        emit_progress(&format!("→ {} done", zip_name));
-    }
+    // The page asked before invoking this, once for the whole profile
+    profiles::run_profile(downloads.inner(), &catalog, &profile, &apps::default_download_dir()?, &progress, &Confirmed).await
+}
 
-    emit_progress("✅ All files extracted successfully!");
-    Ok(format!("✅ Roblox {} downloaded successfully to {}", binary_type, output_dir.display()))
-}
-
-#[tauri::command]
-async fn get_saved_versions() -> Result<Vec<VersionInfo>, String> {
-    let client = reqwest::Client::new();
-    let response = client.get("https://pastebin.com/raw/vgqfphAY")
-        .timeout(std::time::Duration::from_secs(10))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch saved versions: {}", e))?;
-
-    let json_text = response.text().await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-
-    let data: serde_json::Value = serde_json::from_str(&json_text)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-
-    let mut versions = Vec::new();
-    if let Value::Object(map) = data {
-        for (full_ver, description) in map {
-            let hash = if full_ver.to_lowercase().starts_with("version-") {
-                full_ver[8..].to_string()
-            } else {
-                full_ver
-            };
-            
-            let desc = description.as_str().unwrap_or("").to_string();
-            versions.push(VersionInfo { hash, description: desc });
-        }
-    }
+// Takes a catalog id like download_app, the page never picks the URL
+#[tauri::command]
+async fn download_to_desktop(downloads: tauri::State<'_, DownloadManager>, app_id: String) -> Result<DownloadRecord, AppError> {
+    let catalog = catalog::load()?;
+    let app = catalog.find(&app_id)?;
 
-    versions.sort_by(|a, b| a.hash.cmp(&b.hash));
-    Ok(versions)
+    let download = downloads.enqueue(DownloadRequest::to_desktop(app)?)?;
+    downloads.wait(download.id).await
 }
 
-struct BinaryConfig {
-    blob_dir: String,
-    version_file: String,
+#[tauri::command]
+async fn download_atlas_tools(
+    client: tauri::State<'_, SharedClient>,
+    downloads: tauri::State<'_, DownloadManager>,
+) -> Result<DownloadRecord, AppError> {
+    let wizard = tools::download_atlas_tools(client.inner().as_ref()).await?;
+    downloads.add_completed("AME Wizard", tools::AME_WIZARD_URL, wizard)
 }
 
-fn get_binary_configs() -> HashMap<String, BinaryConfig> {
-    let mut configs = HashMap::new();
-    
-    configs.insert("WindowsPlayer".to_string(), BinaryConfig {
-        blob_dir: "/".to_string(),
-        version_file: "/version".to_string(),
-    });
-    
-    configs.insert("WindowsStudio64".to_string(), BinaryConfig {
-        blob_dir: "/".to_string(),
-        version_file: "/versionQTStudio".to_string(),
-    });
-    
-    configs.insert("MacPlayer".to_string(), BinaryConfig {
-        blob_dir: "/mac/".to_string(),
-        version_file: "/mac/version".to_string(),
-    });
-    
-    configs.insert("MacStudio".to_string(), BinaryConfig {
-        blob_dir: "/mac/".to_string(),
-        version_file: "/mac/versionStudio".to_string(),
-    });
-    
-    configs
-}
-
-fn get_player_extract_roots() -> HashMap<String, String> {
-    let mut roots = HashMap::new();
-    
-    roots.insert("RobloxApp.zip".to_string(), "".to_string());
-    roots.insert("redist.zip".to_string(), "".to_string());
-    roots.insert("shaders.zip".to_string(), "shaders/".to_string());
-    roots.insert("ssl.zip".to_string(), "ssl/".to_string());
-    roots.insert("WebView2.zip".to_string(), "".to_string());
-    roots.insert("WebView2RuntimeInstaller.zip".to_string(), "WebView2RuntimeInstaller/".to_string());
-    roots.insert("content-avatar.zip".to_string(), "content/avatar/".to_string());
-    roots.insert("content-configs.zip".to_string(), "content/configs/".to_string());
-    roots.insert("content-fonts.zip".to_string(), "content/fonts/".to_string());
-    roots.insert("content-sky.zip".to_string(), "content/sky/".to_string());
-    roots.insert("content-sounds.zip".to_string(), "content/sounds/".to_string());
-    roots.insert("content-textures2.zip".to_string(), "content/textures/".to_string());
-    roots.insert("content-models.zip".to_string(), "content/models/".to_string());
-    roots.insert("content-platform-fonts.zip".to_string(), "PlatformContent/pc/fonts/".to_string());
-    roots.insert("content-platform-dictionaries.zip".to_string(), "PlatformContent/pc/shared_compression_dictionaries/".to_string());
-    roots.insert("content-terrain.zip".to_string(), "PlatformContent/pc/terrain/".to_string());
-    roots.insert("content-textures3.zip".to_string(), "PlatformContent/pc/textures/".to_string());
-    roots.insert("extracontent-luapackages.zip".to_string(), "ExtraContent/LuaPackages/".to_string());
-    roots.insert("extracontent-translations.zip".to_string(), "ExtraContent/translations/".to_string());
-    roots.insert("extracontent-models.zip".to_string(), "ExtraContent/models/".to_string());
-    roots.insert("extracontent-textures.zip".to_string(), "ExtraContent/textures/".to_string());
-    roots.insert("extracontent-places.zip".to_string(), "ExtraContent/places/".to_string());
-    
-    roots
-}
-
-fn get_studio_extract_roots() -> HashMap<String, String> {
-    let mut roots = HashMap::new();
-    
-    roots.insert("RobloxStudio.zip".to_string(), "".to_string());
-    roots.insert("RibbonConfig.zip".to_string(), "RibbonConfig/".to_string());
-    roots.insert("redist.zip".to_string(), "".to_string());
-    roots.insert("Libraries.zip".to_string(), "".to_string());
-    roots.insert("LibrariesQt5.zip".to_string(), "".to_string());
-    roots.insert("WebView2.zip".to_string(), "".to_string());
-    roots.insert("WebView2RuntimeInstaller.zip".to_string(), "WebView2RuntimeInstaller/".to_string());
-    roots.insert("shaders.zip".to_string(), "shaders/".to_string());
-    roots.insert("ssl.zip".to_string(), "ssl/".to_string());
-    roots.insert("Qml.zip".to_string(), "Qml/".to_string());
-    roots.insert("Plugins.zip".to_string(), "Plugins/".to_string());
-    roots.insert("StudioFonts.zip".to_string(), "StudioFonts/".to_string());
-    roots.insert("BuiltInPlugins.zip".to_string(), "BuiltInPlugins/".to_string());
-    roots.insert("ApplicationConfig.zip".to_string(), "ApplicationConfig/".to_string());
-    roots.insert("BuiltInStandalonePlugins.zip".to_string(), "BuiltInStandalonePlugins/".to_string());
-    roots.insert("content-qt_translations.zip".to_string(), "content/qt_translations/".to_string());
-    roots.insert("content-sky.zip".to_string(), "content/sky/".to_string());
-    roots.insert("content-fonts.zip".to_string(), "content/fonts/".to_string());
-    roots.insert("content-avatar.zip".to_string(), "content/avatar/".to_string());
-    roots.insert("content-models.zip".to_string(), "content/models/".to_string());
-    roots.insert("content-sounds.zip".to_string(), "content/sounds/".to_string());
-    roots.insert("content-configs.zip".to_string(), "content/configs/".to_string());
-    roots.insert("content-api-docs.zip".to_string(), "content/api_docs/".to_string());
-    roots.insert("content-textures2.zip".to_string(), "content/textures/".to_string());
-    roots.insert("content-studio_svg_textures.zip".to_string(), "content/studio_svg_textures/".to_string());
-    roots.insert("content-platform-fonts.zip".to_string(), "PlatformContent/pc/fonts/".to_string());
-    roots.insert("content-platform-dictionaries.zip".to_string(), "PlatformContent/pc/shared_compression_dictionaries/".to_string());
-    roots.insert("content-terrain.zip".to_string(), "PlatformContent/pc/terrain/".to_string());
-    roots.insert("content-textures3.zip".to_string(), "PlatformContent/pc/textures/".to_string());
-    roots.insert("extracontent-translations.zip".to_string(), "ExtraContent/translations/".to_string());
-    roots.insert("extracontent-luapackages.zip".to_string(), "ExtraContent/LuaPackages/".to_string());
-    roots.insert("extracontent-textures.zip".to_string(), "ExtraContent/textures/".to_string());
-    roots.insert("extracontent-scripts.zip".to_string(), "ExtraContent/scripts/".to_string());
-    roots.insert("extracontent-models.zip".to_string(), "ExtraContent/models/".to_string());
-    
-    roots
-}
-
-#[tauri::command]
-async fn download_app(url: String, filename: String) -> Result<String, String> {
-    let downloads_dir = dirs::download_dir()
-        .ok_or_else(|| "Could not find Downloads directory".to_string())?;
-    
-    // Create tfy-downloads subdirectory
-    let tfy_downloads = downloads_dir.join("tfy-downloads");
-    fs::create_dir_all(&tfy_downloads)
-        .map_err(|e| format!("Failed to create tfy-downloads directory: {}", e))?;
-    
-    let file_path = tfy_downloads.join(&filename);
-    
-    // Download the file
-    let client = reqwest::Client::new();
-    let response = client.get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download: {}", e))?;
-    
-    let bytes = response.bytes().await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-    
-    // Write to file
-    fs::write(&file_path, &bytes)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-    
-    // Try to execute the file with multiple fallback methods
-    match Command::new(&file_path)
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .spawn() 
-    {
-        Ok(_) => Ok(format!("✅ {} downloaded and launched successfully!", filename)),
-        Err(_) => {
-            // Fallback 1: Try with explorer
-            match Command::new("explorer")
-                .arg(&file_path)
-                .creation_flags(0x08000000) // CREATE_NO_WINDOW
-                .spawn() 
-            {
-                Ok(_) => Ok(format!("✅ {} downloaded and opened with explorer!", filename)),
-                Err(_) => {
-                    // Fallback 2: Try with cmd /c
-                    match Command::new("cmd")
-                        .args(&["/c", &format!("\"{}\"", file_path.display())])
-                        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-                        .status()
-                    {
-                        Ok(status) if status.success() => {
-                            Ok(format!("✅ {} downloaded and launched via cmd!", filename))
-                        }
-                        _ => {
-                            Ok(format!("✅ {} downloaded to {}. Please run it manually if it didn't start automatically.", filename, file_path.display()))
-                        }
-                    }
-                }
-            }
-        }
-    }
+#[tauri::command]
+fn list_downloads(downloads: tauri::State<'_, DownloadManager>) -> Vec<DownloadRecord> {
+    downloads.list()
 }
 
 #[tauri::command]
-async fn download_to_desktop_and_run(name: String, url: String) -> Result<String, String> {
-    let desktop_dir = dirs::desktop_dir()
-        .ok_or_else(|| "Could not find Desktop directory".to_string())?;
-    
-    let file_path = desktop_dir.join(format!("{}.exe", name));
-    
-    // Download the file
-    let client = reqwest::Client::new();
-    let response = client.get(&url)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download: {}", e))?;
-    
-    let bytes = response.bytes().await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-    
-    // Write to file
-    fs::write(&file_path, &bytes)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-    
-    // Try to execute the file with multiple fallback methods
-    match Command::new(&file_path)
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .spawn() 
-    {
-        Ok(_) => Ok(format!("✅ {} downloaded and launched successfully!", name)),
-        Err(_) => {
-            // Fallback 1: Try with explorer
-            match Command::new("explorer")
-                .arg(&file_path)
-                .creation_flags(0x08000000) // CREATE_NO_WINDOW
-                .spawn() 
-            {
-                Ok(_) => Ok(format!("✅ {} downloaded and opened with explorer!", name)),
-                Err(_) => {
-                    // Fallback 2: Try with cmd /c
-                    match Command::new("cmd")
-                        .args(&["/c", &format!("\"{}\"", file_path.display())])
-                        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-                        .status()
-                    {
-                        Ok(status) if status.success() => {
-                            Ok(format!("✅ {} downloaded and launched via cmd!", name))
-                        }
-                        _ => {
-                            Ok(format!("✅ {} downloaded to {}. Please run it manually if it didn't start automatically.", name, file_path.display()))
-                        }
-                    }
-                }
-            }
-        }
-    }
+fn pause_download(downloads: tauri::State<'_, DownloadManager>, id: u64) -> Result<DownloadRecord, AppError> {
+    downloads.pause(id)
 }
 
+// Async like download_app: requeueing starts the transfer with tokio::spawn,
+// which needs the runtime that sync commands don't run on
 #[tauri::command]
-fn get_username() -> String {
-    whoami::username()
+async fn resume_download(downloads: tauri::State<'_, DownloadManager>, id: u64) -> Result<DownloadRecord, AppError> {
+    downloads.resume(id)
 }
 
 #[tauri::command]
-async fn run_function(name: String, args: Option<String>) -> Result<String, String> {
-    match name.as_str() {
-        "winrar_crack" => winrar_crack().await,
-        "wifi_passwords" => wifi_passwords().await,
-        "activate_windows" => activate_windows().await,
-        "run_optimization" => run_optimization().await,
-        "clean_temp" => clean_temp().await,
-        "install_atlas_tools" => install_atlas_tools().await,
-        _ => Err(format!("Unknown function: {}", name))
-    }
+fn cancel_download(downloads: tauri::State<'_, DownloadManager>, id: u64) -> Result<DownloadRecord, AppError> {
+    downloads.cancel(id)
 }
 
-async fn winrar_crack() -> Result<String, String> {
-    let client = reqwest::Client::new();
-    let response = client.get("https://github.com/jtlw99/crack-winrar/releases/download/v1/rarreg.key")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download crack: {}", e))?;
-    
-    let key_content = response.bytes().await
-        .map_err(|e| format!("Failed to read crack content: {}", e))?;
-    
-    let paths = [
-        "C:\\Program Files\\WinRAR\\rarreg.key",
-        "C:\\Program Files (x86)\\WinRAR\\rarreg.key"
-    ];
-    
-    for path in &paths {
-        if let Ok(_) = fs::write(path, &key_content) {
-            return Ok(format!("✅ WinRAR crack applied successfully to {}", path));
-        }
-    }
-    
-    Err("❌ No valid WinRAR installation found".to_string())
-}
-
-async fn wifi_passwords() -> Result<String, String> {
-    let output = Command::new("cmd")
-        .args(&["/C", "netsh wlan show profiles"])
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .output()
-        .map_err(|e| format!("Failed to get WiFi profiles: {}", e))?;
-    
-    let profiles_output = String::from_utf8_lossy(&output.stdout);
-    let mut passwords = String::new();
-    
-    for line in profiles_output.lines() {
-        if line.contains("All User Profile") {
-            if let Some(profile_name) = line.split(':').nth(1) {
-                let profile_name = profile_name.trim();
-                
-                let password_output = Command::new("cmd")
-                    .args(&["/C", &format!("netsh wlan show profile name=\"{}\" key=clear", profile_name)])
-                    .creation_flags(0x08000000) // CREATE_NO_WINDOW
-                    .output();
-                
-                if let Ok(pwd_output) = password_output {
-                    let pwd_text = String::from_utf8_lossy(&pwd_output.stdout);
-                    for pwd_line in pwd_text.lines() {
-                        if pwd_line.contains("Key Content") {
-                            if let Some(password) = pwd_line.split(':').nth(1) {
-                                passwords.push_str(&format!("{}: {}\n", profile_name, password.trim()));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
-    if passwords.is_empty() {
-        Ok("No WiFi passwords found.".to_string())
-    } else {
-        // Create a temporary file with the passwords and open it in notepad
-        let temp_dir = std::env::temp_dir();
-        let temp_file = temp_dir.join("wifi_passwords.txt");
-        fs::write(&temp_file, &passwords)
-            .map_err(|e| format!("Failed to write passwords file: {}", e))?;
-        
-        // Open the file in notepad
-        Command::new("notepad")
-            .arg(&temp_file)
-            .creation_flags(0x08000000) // CREATE_NO_WINDOW
-            .spawn()
-            .map_err(|e| format!("Failed to open notepad: {}", e))?;
-        
-        Ok("✅ WiFi passwords saved and opened in Notepad!".to_string())
-    }
+#[tauri::command]
+async fn retry_download(downloads: tauri::State<'_, DownloadManager>, id: u64) -> Result<DownloadRecord, AppError> {
+    downloads.retry(id)
 }
 
-async fn activate_windows() -> Result<String, String> {
-    let status = Command::new("powershell")
-        .args(&["-Command", "irm https://get.activated.win | iex"])
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .status()
-        .map_err(|e| format!("Failed to run activation: {}", e))?;
-    
-    if status.success() {
-        Ok("✅ Windows activation process started!".to_string())
-    } else {
-        Err("❌ Failed to start Windows activation".to_string())
-    }
+#[tauri::command]
+fn clear_history(downloads: tauri::State<'_, DownloadManager>) -> Result<usize, AppError> {
+    downloads.clear_history()
 }
 
-async fn run_optimization() -> Result<String, String> {
-    let client = reqwest::Client::new();
-    let response = client.get("https://raw.githubusercontent.com/DragosKissLove/testbot/main/TFY%20Optimization.bat")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download optimization script: {}", e))?;
-    
-    let bat_content = response.text().await
-        .map_err(|e| format!("Failed to read script content: {}", e))?;
-    
-    let temp_dir = std::env::temp_dir();
-    let temp_path = temp_dir.join("TFY_Optimization.bat");
-    
-    fs::write(&temp_path, bat_content)
-        .map_err(|e| format!("Failed to write script: {}", e))?;
-    
-    let status = Command::new("powershell")
-        .args(&["-Command", &format!("Start-Process '{}' -Verb RunAs", temp_path.display())])
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .status()
-        .map_err(|e| format!("Failed to run optimization: {}", e))?;
-    
-    if status.success() {
-        Ok("✅ TFY Optimization started successfully!".to_string())
-    } else {
-        Err("❌ Failed to start optimization".to_string())
-    }
+// Downloads are only started from here, after the UI has shown the record
+// (or the user has switched on auto-run)
+#[tauri::command]
+fn run_downloaded_file(downloads: tauri::State<'_, DownloadManager>, id: u64) -> Result<String, AppError> {
+    let download = downloads.run(id)?;
+    Ok(format!("✅ Started {}", download.request.name))
 }
 
-async fn clean_temp() -> Result<String, String> {
-    let status = Command::new("cmd")
-        .args(&["/C", "del /s /f /q %temp%\\* && del /s /f /q C:\\Windows\\Temp\\*"])
-        .creation_flags(0x08000000) // CREATE_NO_WINDOW
-        .status()
-        .map_err(|e| format!("Failed to clean temp files: {}", e))?;
-    
-    if status.success() {
-        Ok("✅ Temporary files cleaned successfully!".to_string())
-    } else {
-        Err("❌ Failed to clean temporary files".to_string())
-    }
+#[tauri::command]
+fn inspect_executable(path: String) -> Result<PeInfo, AppError> {
+    pe::inspect_file(Path::new(&path))
 }
 
-async fn install_atlas_tools() -> Result<String, String> {
-    let downloads_dir = dirs::download_dir()
-        .ok_or_else(|| "Could not find Downloads directory".to_string())?;
-    
-    let client = reqwest::Client::new();
-    
-    // Download Atlas Playbook
-    let atlas_url = "https://github.com/Atlas-OS/Atlas/releases/download/0.4.1/AtlasPlaybook_v0.4.1.apbx";
-    let atlas_response = client.get(atlas_url)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download Atlas Playbook: {}", e))?;
-    
-    let atlas_path = downloads_dir.join("AtlasPlaybook_v0.4.1.apbx");
-    fs::write(&atlas_path, atlas_response.bytes().await.unwrap())
-        .map_err(|e| format!("Failed to save Atlas Playbook: {}", e))?;
-    
-    // Download AME Wizard
-    let ame_url = "https://download.ameliorated.io/AME%20Wizard%20Beta.zip";
-    let ame_response = client.get(ame_url)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download AME Wizard: {}", e))?;
-    
-    let ame_zip_path = downloads_dir.join("AME_Wizard_Beta.zip");
-    fs::write(&ame_zip_path, ame_response.bytes().await.unwrap())
-        .map_err(|e| format!("Failed to save AME Wizard: {}", e))?;
-    
-    // Extract AME Wizard
-    let ame_extract_dir = downloads_dir.join("AME_Wizard_Beta");
-    fs::create_dir_all(&ame_extract_dir)
-        .map_err(|e| format!("Failed to create extraction directory: {}", e))?;
-    
-    let file = fs::File::open(&ame_zip_path)
-        .map_err(|e| format!("Failed to open AME zip: {}", e))?;
-    
-    let mut archive = ZipArchive::new(file)
-        .map_err(|e| format!("Failed to read AME zip: {}", e))?;
-    
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)
-            .map_err(|e| format!("Failed to read file from AME zip: {}", e))?;
-        
-        if file.is_dir() {
-            continue;
-        }
-        
-        let file_path = ame_extract_dir.join(file.name());
-        
-        if let Some(parent) = file_path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create directory: {}", e))?;
-        }
-        
-        let mut output_file = fs::File::create(&file_path)
-            .map_err(|e| format!("Failed to create extracted file: {}", e))?;
-        
-        std::io::copy(&mut file, &mut output_file)
-            .map_err(|e| format!("Failed to extract file: {}", e))?;
-        
-        // If this is an exe file, try to run it
-        if file_path.extension().and_then(|s| s.to_str()) == Some("exe") {
-            let _ = Command::new(&file_path)
-                .creation_flags(0x08000000) // CREATE_NO_WINDOW
-                .spawn();
-        }
-    }
-    
-    Ok("✅ Atlas tools downloaded and AME Wizard launched!".to_string())
+#[tauri::command]
+fn get_username() -> String {
+    whoami::username()
+}
+
+#[tauri::command]
+async fn run_function(
+    client: tauri::State<'_, SharedClient>,
+    name: String,
+    args: Option<String>,
+) -> Result<String, AppError> {
+    tools::run_function(client.inner().as_ref(), &name).await
 }
 
 fn main() {
+    // Leftovers from installs that were interrupted by a crash or a closed app.
+    // Installs the CLI is running right now hold their lock and are left alone.
+    let _ = registry::cleanup_staging();
+
+    let client = tfy_core::http::default_client();
+    let download_manager = DownloadManager::new(client.clone(), downloads::DEFAULT_CONCURRENCY);
+
     tauri::Builder::default()
+        .manage(Launches::default())
+        .manage(client)
+        .manage(download_manager.clone())
+        .setup(move |app| {
+            // Every change goes to the UI, then whatever was queued before the last exit continues
+            let app_handle = app.handle();
+            download_manager.on_change(move |download| {
+                let _ = app_handle.emit_all("download-changed", download);
+            });
+            tauri::async_runtime::spawn(async move {
+                let _ = download_manager.restore();
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             download_player,
             get_saved_versions,
+            get_installed_versions,
+            verify_installed_version,
+            repair_installed_version,
+            dedupe_installs,
+            get_fflags,
+            set_fflag,
+            remove_fflag,
+            list_fflag_presets,
+            save_fflag_preset,
+            delete_fflag_preset,
+            apply_fflag_preset,
+            import_fflags,
+            export_fflags,
+            launch_installed_version,
+            get_running_versions,
+            bisect_start,
+            bisect_status,
+            bisect_install_current,
+            bisect_mark,
+            bisect_reset,
+            get_app_config,
+            save_app_config,
+            export_version,
+            import_version,
+            list_apps,
+            get_app_statuses,
             download_app,
-            download_to_desktop_and_run,
+            install_download,
+            list_profiles,
+            save_profile,
+            delete_profile,
+            import_profile,
+            export_profile,
+            run_profile,
+            download_to_desktop,
+            download_atlas_tools,
+            run_downloaded_file,
+            list_downloads,
+            pause_download,
+            resume_download,
+            cancel_download,
+            retry_download,
+            clear_history,
+            inspect_executable,
             get_username,
             run_function,
             get_system_info
@@ -0,0 +1,486 @@
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tfy_core::apps::DownloadRequest;
+use tfy_core::downloads::{DownloadManager, DEFAULT_CONCURRENCY};
+use tfy_core::installer::{self, find_install, PlayerRequest};
+use tfy_core::profiles::{self, InstallProfile};
+use tfy_core::setup::{self, Confirm, Confirmed, InstallOutcome};
+use tfy_core::versions::{self, DEFAULT_BINARY_TYPE, DEFAULT_CHANNEL};
+use tfy_core::{apps, catalog, detect, disk, http, pe, registry, sysinfo, AppError, NoProgress, ProgressSink};
+
+#[derive(Parser)]
+#[command(name = "tfy-cli", version, about = "Download and manage client versions without the GUI")]
+struct Cli {
+    /// Print results as JSON on stdout, errors as JSON on stderr
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Don't print progress lines
+    #[arg(long, short, global = true)]
+    quiet: bool,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Download, verify and install a client version
+    DownloadPlayer {
+        /// Version hash, with or without the "version-" prefix
+        version: String,
+        #[arg(long, default_value = DEFAULT_CHANNEL)]
+        channel: String,
+        #[arg(long, default_value = DEFAULT_BINARY_TYPE)]
+        binary_type: String,
+        /// Install from pre-downloaded packages instead of the CDN
+        #[arg(long)]
+        package_dir: Option<PathBuf>,
+        /// Fetch packages from this mirror instead of the channel's CDN
+        #[arg(long)]
+        cdn_url: Option<String>,
+        /// Create the version folder in here instead of Downloads
+        #[arg(long)]
+        install_root: Option<PathBuf>,
+    },
+    /// List the saved versions shown on the Extra page
+    ListVersions,
+    /// List installed versions
+    Installed,
+    /// Check an installed version against its file index
+    Verify {
+        version: String,
+        #[arg(long)]
+        binary_type: Option<String>,
+    },
+    /// List the apps that download-app can fetch
+    ListApps {
+        /// Only use the cached or bundled catalog
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Show which catalog apps are installed and whether the catalog has a newer version
+    AppStatus,
+    /// Write the detached signature for a catalog file next to it, as <catalog>.sig
+    SignCatalog {
+        catalog: PathBuf,
+        /// File holding the 32 byte Ed25519 secret key in hex
+        #[arg(long)]
+        key: PathBuf,
+    },
+    /// Download a catalog app into Downloads/tfy-downloads
+    DownloadApp {
+        /// App id from list-apps
+        app: String,
+        /// Save into this folder instead
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Start the file once it is downloaded
+        #[arg(long)]
+        run: bool,
+    },
+    /// List the download history
+    Downloads {
+        /// Remove completed, failed and cancelled downloads from it first
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Download a catalog app and run its installer unattended
+    InstallApp {
+        /// App id from list-apps
+        app: String,
+        /// Save the installer into this folder instead
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Install without asking, like run_mode "auto" in the config
+        #[arg(long)]
+        yes: bool,
+    },
+    /// List the saved install profiles
+    Profiles,
+    /// Create or replace an install profile
+    SaveProfile {
+        name: String,
+        /// App ids from list-apps, installed in this order
+        #[arg(required = true)]
+        apps: Vec<String>,
+    },
+    /// Delete a saved install profile
+    DeleteProfile {
+        name: String,
+    },
+    /// Save a profile file exported on another machine
+    ImportProfile {
+        path: PathBuf,
+    },
+    /// Write a profile to a file for another machine
+    ExportProfile {
+        name: String,
+        path: PathBuf,
+    },
+    /// Download and install every app of a profile, then print a summary
+    RunProfile {
+        name: String,
+        /// Save the installers into this folder instead
+        #[arg(long)]
+        dir: Option<PathBuf>,
+        /// Start without asking, like run_mode "auto" in the config
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Start a file saved by download-app, after checking it has not changed
+    RunDownload {
+        /// Download id printed by download-app
+        id: u64,
+    },
+    /// Show the machine type, version info and signer of a Windows executable
+    Inspect {
+        path: PathBuf,
+    },
+    /// Show OS, CPU, RAM and GPU information
+    Sysinfo,
+}
+
+// Progress goes to stderr so stdout stays parseable
+struct StderrProgress;
+
+impl ProgressSink for StderrProgress {
+    fn progress(&self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+// Asks on stderr and reads the answer from stdin, anything but "y" is a no
+struct TerminalConfirm;
+
+impl Confirm for TerminalConfirm {
+    fn confirm(&self, title: &str, message: &str) -> bool {
+        eprint!("{}\n{}\n[y/N] ", title, message);
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer).is_ok() && answer.trim().eq_ignore_ascii_case("y")
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), AppError> {
+    let text = serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::io("Failed to serialize output", e))?;
+    println!("{}", text);
+    Ok(())
+}
+
+// Exit codes: 0 success, 1 error, 2 verify found damaged files, 3 installed
+// but Windows has to restart
+async fn run(cli: Cli) -> Result<ExitCode, AppError> {
+    let progress: &dyn ProgressSink = if cli.quiet { &NoProgress } else { &StderrProgress };
+    let client = http::default_client();
+
+    match cli.command {
+        Commands::DownloadPlayer { version, channel, binary_type, package_dir, cdn_url, install_root } => {
+            // Interrupted installs are cleaned up before a new one, like on GUI start.
+            // Ones another process is still running hold their lock and are skipped.
+            let _ = registry::cleanup_staging();
+
+            let mut request = PlayerRequest::new(version, channel, binary_type);
+            request.package_dir = package_dir;
+            request.cdn_url = cdn_url;
+            request.install_root = install_root;
+
+            let record = installer::download_player(client.as_ref(), progress, &request).await?;
+            if cli.json {
+                print_json(&record)?;
+            } else {
+                println!("Installed {} ({}) to {}", record.version, record.binary_type, record.path.display());
+            }
+        }
+        Commands::ListVersions => {
+            let versions = versions::fetch_saved_versions(client.as_ref(), versions::SAVED_VERSIONS_URL).await?;
+            if cli.json {
+                print_json(&versions)?;
+            } else {
+                for version in &versions {
+                    println!("version-{}  {}", version.hash, version.description);
+                }
+            }
+        }
+        Commands::Installed => {
+            let installs = registry::load()?.installs;
+            if cli.json {
+                print_json(&installs)?;
+            } else {
+                for install in &installs {
+                    println!("{}  {}  {}  {}", install.version, install.binary_type, install.channel, install.path.display());
+                }
+            }
+        }
+        Commands::Verify { version, binary_type } => {
+            let record = find_install(&version, binary_type.as_deref())?;
+            let damaged = installer::verify_install(&record)?;
+            if cli.json {
+                print_json(&damaged)?;
+            } else if damaged.is_empty() {
+                println!("{} is intact", record.version);
+            } else {
+                for (package, files) in &damaged {
+                    println!("{}:", package);
+                    for file in files {
+                        println!("  {}", file);
+                    }
+                }
+            }
+            if !damaged.is_empty() {
+                return Ok(ExitCode::from(2));
+            }
+        }
+        Commands::ListApps { offline } => {
+            let catalog = if offline {
+                catalog::load()?
+            } else {
+                match catalog::refresh(client.as_ref()).await {
+                    Ok(catalog) => catalog,
+                    Err(error) => {
+                        progress.progress(&format!("Using the cached app catalog: {}", error));
+                        catalog::load()?
+                    }
+                }
+            };
+            if cli.json {
+                print_json(&catalog.apps)?;
+            } else {
+                for app in &catalog.apps {
+                    println!("{:<18} {}", app.id, app.name);
+                }
+            }
+        }
+        Commands::AppStatus => {
+            let catalog = catalog::load()?;
+            let statuses = detect::detect(&catalog.apps, detect::default_detector().as_ref())?;
+            if cli.json {
+                print_json(&statuses)?;
+            } else {
+                for status in &statuses {
+                    let state = match &status.installed {
+                        None => "not installed".to_string(),
+                        Some(app) => format!(
+                            "installed {}{}",
+                            app.version.as_deref().unwrap_or("(unknown version)"),
+                            if status.update_available { ", update available" } else { "" }
+                        ),
+                    };
+                    println!("{:<18} {}", status.id, state);
+                }
+            }
+        }
+        Commands::SignCatalog { catalog: catalog_path, key } => {
+            let read = |path: &PathBuf| {
+                std::fs::read_to_string(path)
+                    .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))
+            };
+            let signature = catalog::sign(&read(&catalog_path)?, &read(&key)?)?;
+
+            let mut signature_path = catalog_path.into_os_string();
+            signature_path.push(".sig");
+            let signature_path = PathBuf::from(signature_path);
+            std::fs::write(&signature_path, &signature)
+                .map_err(|e| AppError::io(format!("Failed to write {}", signature_path.display()), e))?;
+
+            if cli.json {
+                print_json(&serde_json::json!({ "signature": signature, "path": signature_path }))?;
+            } else {
+                println!("Wrote {}", signature_path.display());
+            }
+        }
+        Commands::DownloadApp { app, dir, run } => {
+            let catalog = catalog::load()?;
+            let app = catalog.find(&app)?;
+            let dir = match dir {
+                Some(dir) => dir,
+                None => apps::default_download_dir()?,
+            };
+            let manager = DownloadManager::new(client.clone(), DEFAULT_CONCURRENCY);
+            let queued = manager.enqueue(DownloadRequest::for_app(app, &dir))?;
+            let download = manager.wait(queued.id).await?;
+            // --run is the confirmation, otherwise it waits for run-download
+            if run {
+                manager.run(download.id)?;
+            }
+
+            if cli.json {
+                print_json(&download)?;
+            } else {
+                let size = download.file.as_ref().map(|file| file.size).unwrap_or(0);
+                println!("Saved {} ({}) as download {}", download.path.display(), disk::format_size(size), download.id);
+                println!("{}", download.summary.as_deref().unwrap_or_default());
+                if run {
+                    println!("Started {}", download.request.name);
+                }
+            }
+        }
+        Commands::InstallApp { app, dir, yes } => {
+            let catalog = catalog::load()?;
+            let app = catalog.find(&app)?;
+            let dir = match dir {
+                Some(dir) => dir,
+                None => apps::default_download_dir()?,
+            };
+            let manager = DownloadManager::new(client.clone(), DEFAULT_CONCURRENCY);
+            let confirm: &dyn Confirm = if yes { &Confirmed } else { &TerminalConfirm };
+            let result = setup::install_app(&manager, app, &dir, confirm).await?;
+
+            if cli.json {
+                print_json(&result)?;
+            } else {
+                println!("{}", result.message);
+            }
+            match result.outcome {
+                InstallOutcome::Installed => {}
+                InstallOutcome::RebootRequired => return Ok(ExitCode::from(3)),
+                InstallOutcome::Failed => return Ok(ExitCode::FAILURE),
+            }
+        }
+        Commands::Profiles => {
+            let profiles = profiles::list()?;
+            if cli.json {
+                print_json(&profiles)?;
+            } else {
+                for profile in &profiles {
+                    println!("{:<20} {}", profile.name, profile.apps.join(", "));
+                }
+            }
+        }
+        Commands::SaveProfile { name, apps } => {
+            let profile = InstallProfile::new(&name, apps);
+            profiles::save(&profile, &catalog::load()?)?;
+            if cli.json {
+                print_json(&profile)?;
+            } else {
+                println!("Saved {} with {} apps", profile.name, profile.apps.len());
+            }
+        }
+        Commands::DeleteProfile { name } => {
+            profiles::delete(&name)?;
+            if !cli.json {
+                println!("Deleted {}", name);
+            }
+        }
+        Commands::ImportProfile { path } => {
+            let profile = profiles::import(&path, &catalog::load()?)?;
+            if cli.json {
+                print_json(&profile)?;
+            } else {
+                println!("Imported {}: {}", profile.name, profile.apps.join(", "));
+            }
+        }
+        Commands::ExportProfile { name, path } => {
+            let profile = profiles::export(&name, &path)?;
+            if !cli.json {
+                println!("Exported {} to {}", profile.name, path.display());
+            }
+        }
+        Commands::RunProfile { name, dir, yes } => {
+            let profile = profiles::load(&name)?;
+            let catalog = catalog::load()?;
+            let dir = match dir {
+                Some(dir) => dir,
+                None => apps::default_download_dir()?,
+            };
+            let manager = DownloadManager::new(client.clone(), DEFAULT_CONCURRENCY);
+            let confirm: &dyn Confirm = if yes { &Confirmed } else { &TerminalConfirm };
+            let report = profiles::run_profile(&manager, &catalog, &profile, &dir, progress, confirm).await?;
+
+            if cli.json {
+                print_json(&report)?;
+            } else {
+                for step in &report.steps {
+                    println!("{:<18} {}", step.app_id, step.message);
+                }
+                println!("{}", report.summary);
+            }
+            if report.count(InstallOutcome::Failed) > 0 {
+                return Ok(ExitCode::FAILURE);
+            }
+            if report.reboot_required() {
+                return Ok(ExitCode::from(3));
+            }
+        }
+        Commands::RunDownload { id } => {
+            let download = DownloadManager::new(client.clone(), DEFAULT_CONCURRENCY).run(id)?;
+            if cli.json {
+                print_json(&download)?;
+            } else {
+                println!("Started {} from {}", download.request.name, download.path.display());
+            }
+        }
+        Commands::Downloads { clear } => {
+            let manager = DownloadManager::new(client.clone(), DEFAULT_CONCURRENCY);
+            if clear {
+                let removed = manager.clear_history()?;
+                if !cli.json {
+                    println!("Removed {} finished downloads from the history", removed);
+                }
+            }
+            let history = manager.list();
+            if cli.json {
+                print_json(&history)?;
+            } else {
+                for download in &history {
+                    let status = serde_json::to_value(download.status)
+                        .ok()
+                        .and_then(|status| status.as_str().map(str::to_string))
+                        .unwrap_or_default();
+                    println!("{:>4}  {:<10} {:<20} {}", download.id, status, download.request.name, download.path.display());
+                }
+            }
+        }
+        Commands::Inspect { path } => {
+            let info = pe::inspect_file(&path)?;
+            if cli.json {
+                print_json(&info)?;
+            } else {
+                println!("Machine:   {} {}", info.machine, info.subsystem);
+                println!("Company:   {}", info.company_name.as_deref().unwrap_or("-"));
+                println!("Product:   {}", info.product_name.as_deref().unwrap_or("-"));
+                println!("Version:   {}", info.file_version.as_deref().unwrap_or("-"));
+                let signer = match (&info.signer, info.signed) {
+                    (Some(signer), _) => signer.as_str(),
+                    (None, true) => "signed, signer unreadable",
+                    (None, false) => "not signed",
+                };
+                // Read from the certificate, not checked against a trusted root
+                println!("Signature: {} (not verified)", signer);
+            }
+        }
+        Commands::Sysinfo => {
+            let info = sysinfo::system_info();
+            if cli.json {
+                print_json(&info)?;
+            } else {
+                println!("OS:  {}", info.os);
+                println!("CPU: {}", info.cpu);
+                println!("RAM: {}", info.ram);
+                println!("GPU: {}", info.gpu);
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.json;
+
+    match run(cli).await {
+        Ok(code) => code,
+        Err(error) => {
+            if json {
+                eprintln!("{}", serde_json::to_string(&error).unwrap_or_else(|_| error.to_string()));
+            } else {
+                eprintln!("error: {}", error);
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
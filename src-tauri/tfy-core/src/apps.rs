@@ -0,0 +1,356 @@
+use crate::catalog::CatalogApp;
+use crate::disk;
+use crate::error::AppError;
+use crate::filename::{self, FileKind};
+use crate::http::{HttpClient, HttpResponse};
+use crate::packages::HashingWriter;
+use crate::pe::{self, PeInfo};
+use crate::process::command;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+pub fn default_download_dir() -> Result<PathBuf, AppError> {
+    let downloads_dir = dirs::download_dir()
+        .ok_or_else(|| AppError::not_found("Could not find Downloads directory"))?;
+    Ok(downloads_dir.join("tfy-downloads"))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DownloadedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    // Lowercase hex of what was actually saved
+    pub sha256: String,
+    // True when the hash matched a pinned one, false when there was nothing to compare against
+    pub verified: bool,
+    // PE headers, version info and signer, None when the file is not an executable
+    pub executable: Option<PeInfo>,
+}
+
+impl DownloadedFile {
+    // Hashes and inspects a file that is already on disk, e.g. one unpacked from an archive
+    pub fn read(path: &Path) -> Result<DownloadedFile, AppError> {
+        let sha256 = hash_file(path)?;
+        let size = fs::metadata(path)
+            .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?
+            .len();
+
+        Ok(DownloadedFile {
+            path: path.to_path_buf(),
+            size,
+            sha256,
+            verified: false,
+            executable: pe::inspect_file(path).ok(),
+        })
+    }
+
+    // One line for the UI, so unpinned downloads can at least be compared by hand
+    pub fn describe_hash(&self) -> String {
+        if self.verified {
+            format!("SHA-256 verified: {}", self.sha256)
+        } else {
+            format!("SHA-256 (not pinned, compare with the publisher): {}", self.sha256)
+        }
+    }
+
+    // describe_hash() plus what the executable says about itself
+    pub fn describe(&self) -> String {
+        match &self.executable {
+            Some(info) => format!("{}\n{}", info.describe(), self.describe_hash()),
+            None => self.describe_hash(),
+        }
+    }
+}
+
+pub fn hash_file(path: &Path) -> Result<String, AppError> {
+    let mut file = fs::File::open(path)
+        .map_err(|e| AppError::io(format!("Failed to open {}", path.display()), e))?;
+    let mut writer = HashingWriter::new(std::io::sink());
+    std::io::copy(&mut file, &mut writer)
+        .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?;
+    Ok(format!("{:x}", writer.hasher.finalize()))
+}
+
+// Streams `response` into `partial` while hashing it, then moves it to `name`
+// in `dir` once the size and `expected` hash check out, so a file that could be
+// executed is always a verified one. The extension of `name` is corrected from
+// the first bytes of the file on the way. A response to a range request is
+// appended after the first `offset` bytes already in `partial`.
+async fn save_verified(
+    response: HttpResponse,
+    partial: &Path,
+    offset: u64,
+    dir: &Path,
+    name: &str,
+    expected: Option<&str>,
+) -> Result<DownloadedFile, AppError> {
+    let mut writer = open_partial(partial, offset)?;
+
+    let written = response.write_to(&mut writer).await;
+    let sha256 = format!("{:x}", writer.hasher.finalize());
+    drop(writer.inner);
+
+    if let Err(e) = written {
+        remove_partial(partial);
+        return Err(e);
+    }
+
+    if let Some(expected) = expected {
+        if !sha256.eq_ignore_ascii_case(expected) {
+            remove_partial(partial);
+            return Err(AppError::validation(format!(
+                "Checksum mismatch for {}: expected {}, got {}. The file was deleted and not run.",
+                name, expected, sha256
+            )));
+        }
+    }
+
+    let kind = filename::sniff(&read_head(partial)?);
+    let name = filename::with_kind(name, kind);
+    let path = unused_path(dir, &name);
+    fs::rename(partial, &path)
+        .map_err(|e| AppError::io(format!("Failed to move download to {}", path.display()), e))?;
+    let _ = fs::remove_file(validator_path(partial));
+
+    // Installers and archives can be large, only executables are worth opening
+    let executable = match kind {
+        Some(FileKind::Pe) => pe::inspect_file(&path).ok(),
+        _ => None,
+    };
+    Ok(DownloadedFile {
+        executable,
+        path,
+        size: writer.size,
+        sha256,
+        verified: expected.is_some(),
+    })
+}
+
+// `name` in `dir`, or "name (1).exe" and up when that is taken, so an earlier
+// download or a file the user kept there is never replaced
+fn unused_path(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.join(name);
+    let mut n = 1;
+    while path.symlink_metadata().is_ok() {
+        path = dir.join(filename::numbered(name, n));
+        n += 1;
+    }
+    path
+}
+
+// `partial` cut to `offset` bytes, with those already hashed
+fn open_partial(partial: &Path, offset: u64) -> Result<HashingWriter<fs::File>, AppError> {
+    if offset == 0 {
+        let file = fs::File::create(partial)
+            .map_err(|e| AppError::io(format!("Failed to create {}", partial.display()), e))?;
+        return Ok(HashingWriter::new(file));
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(partial)
+        .and_then(|mut file| {
+            file.set_len(offset)?;
+            file.seek(SeekFrom::Start(0))?;
+            Ok(file)
+        })
+        .map_err(|e| AppError::io(format!("Failed to open {}", partial.display()), e))?;
+
+    let mut prefix = HashingWriter::new(std::io::sink());
+    std::io::copy(&mut (&mut file).take(offset), &mut prefix)
+        .map_err(|e| AppError::io(format!("Failed to read {}", partial.display()), e))?;
+    let mut writer = HashingWriter::new(file);
+    writer.hasher = prefix.hasher;
+    writer.size = prefix.size;
+    Ok(writer)
+}
+
+// Enough of the start of a file for filename::sniff()
+fn read_head(path: &Path) -> Result<Vec<u8>, AppError> {
+    let mut head = Vec::with_capacity(8);
+    fs::File::open(path)
+        .and_then(|file| file.take(8).read_to_end(&mut head))
+        .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?;
+    Ok(head)
+}
+
+// Everything needed to fetch one file. The download queue persists these so
+// that queued and failed downloads can be started again after a restart.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DownloadRequest {
+    pub name: String,
+    pub url: String,
+    pub dir: PathBuf,
+    // Only used when the server does not name the file, see filename::resolve()
+    pub filename: Option<String>,
+    pub sha256: Option<String>,
+    pub claimed_publisher: Option<String>,
+}
+
+impl DownloadRequest {
+    // A catalog app into `dir`, normally default_download_dir()
+    pub fn for_app(app: &CatalogApp, dir: &Path) -> DownloadRequest {
+        DownloadRequest {
+            name: app.name.clone(),
+            url: app.url.clone(),
+            dir: dir.to_path_buf(),
+            filename: Some(app.filename.clone()),
+            sha256: app.sha256.clone(),
+            claimed_publisher: app.claimed_publisher.clone(),
+        }
+    }
+
+    // A catalog app onto the desktop, for the tools on the Extra page
+    pub fn to_desktop(app: &CatalogApp) -> Result<DownloadRequest, AppError> {
+        let desktop_dir = dirs::desktop_dir()
+            .ok_or_else(|| AppError::not_found("Could not find Desktop directory"))?;
+        Ok(DownloadRequest::for_app(app, &desktop_dir))
+    }
+
+    // The name the file gets if the server has nothing better to offer
+    pub fn fallback_name(&self) -> String {
+        filename::sanitize(self.filename.as_deref().unwrap_or(&self.name))
+            .unwrap_or_else(|| "download".to_string())
+    }
+
+    // Where the download goes until the response says otherwise. Its partial
+    // file is always next to this path, whatever the final name turns out to be.
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(self.fallback_name())
+    }
+}
+
+// Where download() streams to before the checks pass. Queued downloads have
+// their own, see DownloadRecord::partial_path().
+pub fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    PathBuf::from(partial)
+}
+
+// The ETag of the response a partial file was started from, or its
+// Last-Modified date when there is no strong ETag. Resuming sends it as
+// If-Range, so a file that changed on the server since is not appended to.
+fn validator_path(partial: &Path) -> PathBuf {
+    let mut validator = partial.as_os_str().to_owned();
+    validator.push(".validator");
+    PathBuf::from(validator)
+}
+
+fn validator(response: &HttpResponse) -> Option<&str> {
+    response.header("etag")
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| response.header("last-modified"))
+}
+
+// Deletes a partial file along with its validator
+pub fn remove_partial(partial: &Path) {
+    let _ = fs::remove_file(partial);
+    let _ = fs::remove_file(validator_path(partial));
+}
+
+pub async fn download(client: &dyn HttpClient, request: &DownloadRequest) -> Result<DownloadedFile, AppError> {
+    fetch(client, request, &partial_path(&request.path()), None).await
+}
+
+// Like download() into `partial`, but carries on after what a paused download
+// left there. Servers that don't answer the range with a 206 send everything
+// again and the download starts over, so do partial files without a validator.
+pub async fn resume(client: &dyn HttpClient, request: &DownloadRequest, partial: &Path) -> Result<DownloadedFile, AppError> {
+    let offset = fs::metadata(partial).map(|metadata| metadata.len()).unwrap_or(0);
+    let validator = fs::read_to_string(validator_path(partial)).ok();
+    let resumed = validator.as_deref().filter(|_| offset > 0).map(|validator| (offset, validator));
+    fetch(client, request, partial, resumed).await
+}
+
+async fn fetch(
+    client: &dyn HttpClient,
+    request: &DownloadRequest,
+    partial: &Path,
+    resumed: Option<(u64, &str)>,
+) -> Result<DownloadedFile, AppError> {
+    // Download the file, the rest of it when part of it is already here and
+    // the server still has the same file
+    let ranged = match resumed {
+        Some((offset, validator)) => Some((client.get_from(&request.url, offset, Some(validator)).await?, offset)),
+        None => None,
+    };
+    let (response, offset) = match ranged {
+        Some((response, offset)) if response.resumes_at(offset) => (response, offset),
+        // The whole file, changed since or from a server that ignores ranges
+        Some((response, _)) if response.status == 200 => (response, 0),
+        _ => (client.get(&request.url, None).await?.error_for_status(&request.name)?, 0),
+    };
+    
+    // Create the download directory
+    fs::create_dir_all(&request.dir)
+        .map_err(|e| AppError::io(format!("Failed to create {}", request.dir.display()), e))?;
+
+    // A new partial file remembers what it was started from
+    if offset == 0 {
+        match validator(&response) {
+            Some(validator) => {
+                fs::write(validator_path(partial), validator)
+                    .map_err(|e| AppError::io(format!("Failed to create {}", validator_path(partial).display()), e))?;
+            }
+            None => {
+                let _ = fs::remove_file(validator_path(partial));
+            }
+        }
+    }
+    
+    // Servers that send Content-Length get checked against free space up front
+    if let Some(length) = response.content_length() {
+        disk::ensure_free_space(&request.dir, length)?;
+    }
+    
+    let name = filename::resolve(response.header("content-disposition"), &response.url, &request.fallback_name());
+    let file = save_verified(response, partial, offset, &request.dir, &name, request.sha256.as_deref()).await?;
+
+    // Only the name in the certificate is compared. A different one means the
+    // wrong file was served, the right one proves nothing about who made it.
+    if let Some(expected) = &request.claimed_publisher {
+        let publisher = file.executable.as_ref().and_then(|info| info.claimed_publisher.as_deref());
+        if publisher != Some(expected.as_str()) {
+            let _ = fs::remove_file(&file.path);
+            return Err(AppError::validation(format!(
+                "{} claims to be signed by {}, expected {}. The file was deleted and not run.",
+                file.path.file_name().unwrap_or_default().to_string_lossy(),
+                publisher.unwrap_or("nobody"),
+                expected
+            )));
+        }
+    }
+
+    Ok(file)
+}
+
+// Starts a file the way a double click would: executables directly, Windows
+// Installer packages through msiexec and anything else with its default handler
+pub fn open_file(file_path: &Path) -> Result<(), AppError> {
+    let extension = file_path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+
+    let mut process = match extension.as_deref() {
+        Some("exe") => command(file_path),
+        Some("msi") => {
+            let mut process = command("msiexec");
+            process.arg("/i").arg(file_path);
+            process
+        }
+        _ => {
+            let mut process = command("explorer");
+            process.arg(file_path);
+            process
+        }
+    };
+
+    process.spawn()
+        .map(|_| ())
+        .map_err(|e| AppError::io(format!("Failed to start {}", file_path.display()), e))
+}
@@ -0,0 +1,192 @@
+use crate::deploy_history;
+use crate::error::AppError;
+use crate::http::HttpClient;
+use crate::versions::{self, cdn_base_url, normalize_version};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Verdict {
+    pub version: String,
+    pub good: bool,
+}
+
+// `versions` is the deploy history slice from the known-good build to the known-bad one.
+// `good` and `bad` index into it and close in on each other with every verdict.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BisectSession {
+    pub channel: String,
+    pub binary_type: String,
+    pub versions: Vec<String>,
+    pub good: usize,
+    pub bad: usize,
+    pub verdicts: Vec<Verdict>,
+}
+
+#[derive(Serialize)]
+pub struct BisectStatus {
+    pub channel: String,
+    pub binary_type: String,
+    pub last_good: String,
+    pub first_bad: String,
+    pub current: Option<String>,
+    pub culprit: Option<String>,
+    pub remaining: usize,
+    pub steps_left: u32,
+    pub verdicts: Vec<Verdict>,
+}
+
+fn session_path() -> Result<PathBuf, AppError> {
+    let data_dir = dirs::data_dir()
+        .ok_or_else(|| AppError::not_found("Could not find app data directory"))?;
+    Ok(data_dir.join("tfy-tool").join("bisect.json"))
+}
+
+pub fn load() -> Result<Option<BisectSession>, AppError> {
+    let path = session_path()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let text = fs::read_to_string(&path)
+        .map_err(|e| AppError::io("Failed to read bisect session", e))?;
+    serde_json::from_str(&text)
+        .map(Some)
+        .map_err(|e| AppError::validation(format!("Failed to parse bisect session: {}", e)))
+}
+
+pub fn clear() -> Result<(), AppError> {
+    let path = session_path()?;
+    if path.exists() {
+        fs::remove_file(&path)
+            .map_err(|e| AppError::io("Failed to remove bisect session", e))?;
+    }
+    Ok(())
+}
+
+// Fetches the deploy history and saves a new session in place of any running one
+pub async fn begin(
+    client: &dyn HttpClient,
+    good_hash: &str,
+    bad_hash: &str,
+    channel: &str,
+    binary_type: &str,
+) -> Result<BisectSession, AppError> {
+    let binary_config = versions::binary_config(binary_type)?;
+
+    let history = deploy_history::fetch_history(client, &cdn_base_url(channel), &binary_config.blob_dir, binary_type).await?;
+
+    let session = BisectSession::start(
+        &history,
+        &normalize_version(good_hash),
+        &normalize_version(bad_hash),
+        channel.to_string(),
+        binary_type.to_string(),
+    )?;
+    session.save()?;
+    Ok(session)
+}
+
+pub fn load_running() -> Result<BisectSession, AppError> {
+    load()?.ok_or_else(|| AppError::not_found("No bisect session in progress"))
+}
+
+pub fn record_verdict(verdict: &str) -> Result<BisectStatus, AppError> {
+    let good = match verdict.to_lowercase().as_str() {
+        "good" => true,
+        "bad" => false,
+        other => return Err(AppError::validation(format!("Unknown verdict: {}", other))),
+    };
+
+    let mut session = load_running()?;
+    session.mark(good)?;
+    session.save()?;
+    Ok(session.status())
+}
+
+impl BisectSession {
+    pub fn start(
+        history: &[String],
+        good: &str,
+        bad: &str,
+        channel: String,
+        binary_type: String,
+    ) -> Result<BisectSession, AppError> {
+        let position = |version: &str| {
+            history.iter()
+                .position(|entry| entry == version)
+                .ok_or_else(|| AppError::not_found(format!("{} is not in the {} deploy history", version, binary_type)))
+        };
+
+        let good_index = position(good)?;
+        let bad_index = position(bad)?;
+        if good_index >= bad_index {
+            return Err(AppError::validation(format!("{} must be deployed before {}", good, bad)));
+        }
+
+        Ok(BisectSession {
+            channel,
+            binary_type: binary_type.clone(),
+            versions: history[good_index..=bad_index].to_vec(),
+            good: 0,
+            bad: bad_index - good_index,
+            verdicts: Vec::new(),
+        })
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        let path = session_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::io("Failed to create app data directory", e))?;
+        }
+
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::io("Failed to serialize bisect session", e))?;
+        fs::write(&path, text)
+            .map_err(|e| AppError::io("Failed to write bisect session", e))
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.bad - self.good <= 1
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        if self.is_done() {
+            None
+        } else {
+            Some(&self.versions[(self.good + self.bad) / 2])
+        }
+    }
+
+    pub fn mark(&mut self, good: bool) -> Result<(), AppError> {
+        let current = self.current()
+            .ok_or_else(|| AppError::validation("Bisect is already finished"))?
+            .to_string();
+        let index = (self.good + self.bad) / 2;
+
+        if good {
+            self.good = index;
+        } else {
+            self.bad = index;
+        }
+        self.verdicts.push(Verdict { version: current, good });
+        Ok(())
+    }
+
+    pub fn status(&self) -> BisectStatus {
+        let remaining = self.bad - self.good - 1;
+        BisectStatus {
+            channel: self.channel.clone(),
+            binary_type: self.binary_type.clone(),
+            last_good: self.versions[self.good].clone(),
+            first_bad: self.versions[self.bad].clone(),
+            current: self.current().map(str::to_string),
+            culprit: if self.is_done() { Some(self.versions[self.bad].clone()) } else { None },
+            remaining,
+            steps_left: (remaining + 1).next_power_of_two().trailing_zeros(),
+            verdicts: self.verdicts.clone(),
+        }
+    }
+}
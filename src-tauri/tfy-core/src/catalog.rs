@@ -0,0 +1,212 @@
+use crate::error::AppError;
+use crate::filename;
+use crate::setup::InstallerKind;
+use crate::http::HttpClient;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// The installable apps on the Apps page. Commands take an app id, so nothing
+// outside this file decides which URLs get downloaded and run.
+//
+// A newer catalog can be published at CATALOG_URL next to a detached Ed25519
+// signature at CATALOG_URL + ".sig" (128 hex characters over the exact file
+// bytes). Only catalogs signed by CATALOG_PUBLIC_KEY are used, the last one
+// accepted is cached as the known-good copy and the bundled one is the floor.
+const BUNDLED_CATALOG: &str = include_str!("../catalog/apps.json");
+const CATALOG_FORMAT: u32 = 1;
+
+pub const CATALOG_URL: &str = "https://raw.githubusercontent.com/DragosKissLove/tfy-electron2312/master/catalog/apps.json";
+const CATALOG_PUBLIC_KEY: [u8; 32] = [
+    0xea, 0x4a, 0x20, 0x96, 0x10, 0x1b, 0xf9, 0x21, 0x76, 0x3b, 0xf5, 0x22, 0x91, 0x19, 0xf4, 0x15,
+    0x65, 0x72, 0xde, 0x16, 0x1b, 0x48, 0x46, 0x4f, 0x9d, 0xaf, 0x24, 0x2c, 0x9a, 0x95, 0x3e, 0x17,
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CatalogApp {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+    pub color: Option<String>,
+    pub url: String,
+    pub filename: String,
+    // Lowercase hex. Left out for vendor "latest" links whose bytes change with every release.
+    pub sha256: Option<String>,
+    // Why `sha256` is left out, every app without one has to say
+    #[serde(default)]
+    pub unpinned: Option<String>,
+    // Common name the .exe's certificate should claim, see pe::PeInfo. It is
+    // not verified, so it only catches a wrong file, `sha256` is what pins one.
+    #[serde(default)]
+    pub claimed_publisher: Option<String>,
+    // How `silent_args` are passed and exit codes read, see setup::InstallerKind
+    #[serde(default)]
+    pub installer: Option<InstallerKind>,
+    // Replaces the kind's default silent flags when set
+    #[serde(default)]
+    pub silent_args: Vec<String>,
+    // Start of the DisplayName it installs under, when that isn't `name`
+    #[serde(default)]
+    pub installed_name: Option<String>,
+    // The version `url` serves, for links pinned to a release
+    #[serde(default)]
+    pub version: Option<String>,
+    // A tool for the Extra page, downloaded to the desktop and not listed on the Apps page
+    #[serde(default)]
+    pub extra: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Catalog {
+    pub format: u32,
+    // Bumped on every publish, an older signed catalog is never taken over a newer one
+    #[serde(default)]
+    pub revision: u64,
+    pub apps: Vec<CatalogApp>,
+}
+
+pub fn parse(text: &str) -> Result<Catalog, AppError> {
+    let catalog: Catalog = serde_json::from_str(text)
+        .map_err(|e| AppError::validation(format!("Failed to parse app catalog: {}", e)))?;
+
+    if catalog.format != CATALOG_FORMAT {
+        return Err(AppError::validation(format!("Unsupported app catalog format: {}", catalog.format)));
+    }
+
+    for (i, app) in catalog.apps.iter().enumerate() {
+        if app.id.is_empty() || catalog.apps[..i].iter().any(|other| other.id == app.id) {
+            return Err(AppError::validation(format!("App catalog has a missing or duplicate id: '{}'", app.id)));
+        }
+        // The filename is joined onto the download folder, so it must stay a plain name
+        if filename::sanitize(&app.filename).as_deref() != Some(app.filename.as_str()) {
+            return Err(AppError::validation(format!("Invalid filename '{}' for {}", app.filename, app.id)));
+        }
+        if let Some(sha256) = &app.sha256 {
+            if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(AppError::validation(format!("Invalid sha256 '{}' for {}", sha256, app.id)));
+            }
+        } else if app.unpinned.as_deref().map_or(true, |reason| reason.trim().is_empty()) {
+            return Err(AppError::validation(format!("App catalog has no sha256 for {} and no reason in 'unpinned'", app.id)));
+        }
+    }
+
+    Ok(catalog)
+}
+
+pub fn release_key() -> VerifyingKey {
+    VerifyingKey::from_bytes(&CATALOG_PUBLIC_KEY).expect("embedded catalog key is a valid Ed25519 point")
+}
+
+fn decode_hex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    let text = text.trim();
+    let bytes: Option<Vec<u8>> = (0..text.len())
+        .step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect();
+    bytes?.try_into().ok()
+}
+
+pub fn parse_signature(text: &str) -> Result<Signature, AppError> {
+    let bytes = decode_hex::<64>(text)
+        .ok_or_else(|| AppError::validation("App catalog signature must be 64 hex encoded bytes"))?;
+    Ok(Signature::from_bytes(&bytes))
+}
+
+// For publishing: `secret_key` is the 32 byte seed in hex, the result goes in the .sig file
+pub fn sign(text: &str, secret_key: &str) -> Result<String, AppError> {
+    let seed = decode_hex::<32>(secret_key)
+        .ok_or_else(|| AppError::validation("Signing key must be 32 hex encoded bytes"))?;
+    parse(text)?;
+
+    let signature = SigningKey::from_bytes(&seed).sign(text.as_bytes());
+    Ok(signature.to_bytes().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+pub fn verify(text: &str, signature: &str, key: &VerifyingKey) -> Result<Catalog, AppError> {
+    let signature = parse_signature(signature)?;
+    key.verify_strict(text.as_bytes(), &signature)
+        .map_err(|_| AppError::validation("App catalog signature is not valid"))?;
+    parse(text)
+}
+
+fn cache_paths() -> Result<(PathBuf, PathBuf), AppError> {
+    let data_dir = dirs::data_dir()
+        .ok_or_else(|| AppError::not_found("Could not find app data directory"))?;
+    let dir = data_dir.join("tfy-tool");
+    Ok((dir.join("catalog.json"), dir.join("catalog.json.sig")))
+}
+
+// The cached copy is checked again on every load, the cache folder is not trusted
+fn load_cached(key: &VerifyingKey) -> Option<Catalog> {
+    let (catalog_path, signature_path) = cache_paths().ok()?;
+    let text = fs::read_to_string(catalog_path).ok()?;
+    let signature = fs::read_to_string(signature_path).ok()?;
+    verify(&text, &signature, key).ok()
+}
+
+fn store_cached(text: &str, signature: &str) -> Result<(), AppError> {
+    let (catalog_path, signature_path) = cache_paths()?;
+    if let Some(parent) = catalog_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io("Failed to create app data directory", e))?;
+    }
+
+    // Signature last, a crash in between leaves a pair that fails verification
+    fs::write(&catalog_path, text)
+        .map_err(|e| AppError::io("Failed to write cached app catalog", e))?;
+    fs::write(&signature_path, signature)
+        .map_err(|e| AppError::io("Failed to write cached app catalog signature", e))
+}
+
+// The last known-good catalog without touching the network
+pub fn load() -> Result<Catalog, AppError> {
+    load_with(&release_key())
+}
+
+pub fn load_with(key: &VerifyingKey) -> Result<Catalog, AppError> {
+    let bundled = parse(BUNDLED_CATALOG)?;
+    match load_cached(key) {
+        Some(cached) if cached.revision >= bundled.revision => Ok(cached),
+        _ => Ok(bundled),
+    }
+}
+
+pub async fn refresh(client: &dyn HttpClient) -> Result<Catalog, AppError> {
+    refresh_from(client, CATALOG_URL, &release_key()).await
+}
+
+// Fetches and verifies the remote catalog and caches it. On any error the
+// caller keeps using load(), which still returns the previous good copy.
+pub async fn refresh_from(client: &dyn HttpClient, url: &str, key: &VerifyingKey) -> Result<Catalog, AppError> {
+    let timeout = Some(Duration::from_secs(15));
+    let text = client.get(url, timeout).await?.error_for_status("app catalog")?.text().await?;
+
+    let signature_url = format!("{}.sig", url);
+    let signature = client.get(&signature_url, timeout)
+        .await?
+        .error_for_status("app catalog signature")?
+        .text()
+        .await?;
+
+    let catalog = verify(&text, &signature, key)?;
+    let current = load_with(key)?;
+    if catalog.revision < current.revision {
+        return Err(AppError::validation(format!(
+            "Remote app catalog revision {} is older than revision {}",
+            catalog.revision, current.revision
+        )));
+    }
+
+    store_cached(&text, signature.trim())?;
+    Ok(catalog)
+}
+
+impl Catalog {
+    pub fn find(&self, id: &str) -> Result<&CatalogApp, AppError> {
+        self.apps.iter()
+            .find(|app| app.id == id)
+            .ok_or_else(|| AppError::not_found(format!("Unknown app: {}", id)))
+    }
+}
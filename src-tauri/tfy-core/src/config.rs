@@ -0,0 +1,171 @@
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_APP_SETTINGS_TEMPLATE: &str = r#"<?xml version="1.0" encoding="UTF-8"?><Settings><ContentFolder>{content_folder}</ContentFolder><BaseUrl>{base_url}</BaseUrl></Settings>"#;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AppConfig {
+    pub app_settings: AppSettingsConfig,
+    pub launch: LaunchConfig,
+    pub downloads: DownloadsConfig,
+}
+
+// Command put in front of the executable on Linux hosts, e.g. ["wine"]
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LaunchConfig {
+    pub wrapper: Option<Vec<String>>,
+}
+
+// What happens once a download finishes: Ask shows the file's details and
+// waits for a confirmation, Auto runs it straight away
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RunMode {
+    #[default]
+    Ask,
+    Auto,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DownloadsConfig {
+    pub run_mode: RunMode,
+}
+
+// A template of None skips AppSettings.xml for that binary kind
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AppSettingsConfig {
+    pub content_folder: String,
+    pub base_url: String,
+    pub player_template: Option<String>,
+    pub studio_template: Option<String>,
+}
+
+impl Default for AppSettingsConfig {
+    fn default() -> Self {
+        AppSettingsConfig {
+            content_folder: "content".to_string(),
+            base_url: "http://www.roblox.com".to_string(),
+            player_template: Some(DEFAULT_APP_SETTINGS_TEMPLATE.to_string()),
+            studio_template: Some(DEFAULT_APP_SETTINGS_TEMPLATE.to_string()),
+        }
+    }
+}
+
+fn config_path() -> Result<PathBuf, AppError> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| AppError::not_found("Could not find config directory"))?;
+    Ok(config_dir.join("tfy-tool").join("config.json"))
+}
+
+pub fn load() -> Result<AppConfig, AppError> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(AppConfig::default());
+    }
+
+    let text = fs::read_to_string(&path)
+        .map_err(|e| AppError::io("Failed to read config", e))?;
+    serde_json::from_str(&text)
+        .map_err(|e| AppError::validation(format!("Failed to parse config: {}", e)))
+}
+
+impl AppConfig {
+    pub fn save(&self) -> Result<(), AppError> {
+        let path = config_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::io("Failed to create config directory", e))?;
+        }
+
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::io("Failed to serialize config", e))?;
+        fs::write(&path, text)
+            .map_err(|e| AppError::io("Failed to write config", e))
+    }
+}
+
+pub enum AppSettingsOutcome {
+    Written,
+    Unchanged,
+    KeptExisting,
+    Replaced { backup: PathBuf },
+    Skipped,
+}
+
+impl AppSettingsConfig {
+    pub fn render(&self, binary_type: &str) -> Result<Option<String>, AppError> {
+        let template = if binary_type.contains("Studio") {
+            &self.studio_template
+        } else {
+            &self.player_template
+        };
+
+        let Some(template) = template else {
+            return Ok(None);
+        };
+
+        let xml = template
+            .replace("{content_folder}", &self.content_folder)
+            .replace("{base_url}", &self.base_url);
+        validate_app_settings(&xml)
+            .map_err(|e| AppError::validation(format!("Configured AppSettings.xml template is invalid: {}", e.message())))?;
+        Ok(Some(xml))
+    }
+
+    // Existing valid files are left alone, broken ones are backed up before being replaced
+    pub fn write(&self, binary_type: &str, output_dir: &Path) -> Result<AppSettingsOutcome, AppError> {
+        let Some(xml) = self.render(binary_type)? else {
+            return Ok(AppSettingsOutcome::Skipped);
+        };
+
+        let path = output_dir.join("AppSettings.xml");
+        let mut outcome = AppSettingsOutcome::Written;
+
+        if path.exists() {
+            let existing = fs::read_to_string(&path).unwrap_or_default();
+            if existing.trim() == xml.trim() {
+                return Ok(AppSettingsOutcome::Unchanged);
+            }
+            if validate_app_settings(&existing).is_ok() {
+                return Ok(AppSettingsOutcome::KeptExisting);
+            }
+
+            let backup = output_dir.join("AppSettings.xml.bak");
+            fs::rename(&path, &backup)
+                .map_err(|e| AppError::io("Failed to back up AppSettings.xml", e))?;
+            outcome = AppSettingsOutcome::Replaced { backup };
+        }
+
+        fs::write(&path, xml)
+            .map_err(|e| AppError::io("Failed to write AppSettings.xml", e))?;
+        Ok(outcome)
+    }
+}
+
+pub fn validate_app_settings(xml: &str) -> Result<(), AppError> {
+    let body = xml.trim();
+    let body = match body.strip_prefix("<?xml") {
+        Some(rest) => rest.split_once("?>").map(|(_, rest)| rest.trim()).unwrap_or(""),
+        None => body,
+    };
+
+    if !body.starts_with("<Settings>") || !body.ends_with("</Settings>") {
+        return Err(AppError::validation("missing <Settings> root element"));
+    }
+
+    let content_folder = body.split_once("<ContentFolder>")
+        .and_then(|(_, rest)| rest.split_once("</ContentFolder>"))
+        .map(|(value, _)| value.trim())
+        .ok_or_else(|| AppError::validation("missing <ContentFolder> element"))?;
+    if content_folder.is_empty() {
+        return Err(AppError::validation("<ContentFolder> is empty"));
+    }
+
+    Ok(())
+}
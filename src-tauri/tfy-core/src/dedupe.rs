@@ -0,0 +1,105 @@
+use crate::error::AppError;
+use crate::index::{self, FileIndex};
+use crate::registry::InstallRecord;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Default)]
+pub struct DedupeReport {
+    pub installs_scanned: usize,
+    pub files_linked: usize,
+    pub reflinked: usize,
+    pub hardlinked: usize,
+    pub bytes_saved: u64,
+    pub dry_run: bool,
+    pub errors: Vec<String>,
+}
+
+enum LinkKind {
+    Reflink,
+    Hardlink,
+}
+
+// Replaces `target` with a link to `source` through a temporary name, so a failure
+// never leaves the target missing
+fn link_into_place(source: &Path, target: &Path) -> Result<LinkKind, AppError> {
+    let temporary = target.with_file_name(format!(
+        ".{}.dedupe",
+        target.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+    ));
+    let _ = fs::remove_file(&temporary);
+
+    let kind = if reflink_copy::reflink(source, &temporary).is_ok() {
+        LinkKind::Reflink
+    } else {
+        fs::hard_link(source, &temporary)
+            .map_err(|e| AppError::io(format!("Failed to link {} to {}", target.display(), source.display()), e))?;
+        LinkKind::Hardlink
+    };
+
+    if let Err(e) = fs::rename(&temporary, target) {
+        let _ = fs::remove_file(&temporary);
+        return Err(AppError::io(format!("Failed to replace {}", target.display()), e));
+    }
+    Ok(kind)
+}
+
+pub fn dedupe(installs: &[InstallRecord], dry_run: bool) -> Result<DedupeReport, AppError> {
+    let mut report = DedupeReport { dry_run, ..DedupeReport::default() };
+
+    // Files with the same size and SHA-256 across every indexed install
+    let mut groups: BTreeMap<(u64, String), Vec<PathBuf>> = BTreeMap::new();
+    for install in installs {
+        let Some(file_index) = FileIndex::load(&install.path)? else {
+            continue;
+        };
+        report.installs_scanned += 1;
+
+        for (relative, file) in file_index.files {
+            groups.entry((file.size, file.sha256))
+                .or_default()
+                .push(install.path.join(relative));
+        }
+    }
+
+    for ((size, sha256), paths) in groups {
+        if paths.len() < 2 {
+            continue;
+        }
+
+        // The index can be stale, every file is re-hashed before it is linked
+        let matches = |path: &Path| index::hash_file(path).is_some_and(|hashed| hashed == (size, sha256.clone()));
+
+        let Some(source) = paths.iter().find(|path| matches(path)) else {
+            continue;
+        };
+
+        for target in paths.iter().filter(|path| *path != source) {
+            if same_file::is_same_file(source, target).unwrap_or(false) || !matches(target) {
+                continue;
+            }
+
+            if dry_run {
+                report.files_linked += 1;
+                report.bytes_saved += size;
+                continue;
+            }
+
+            match link_into_place(source, target) {
+                Ok(kind) => {
+                    match kind {
+                        LinkKind::Reflink => report.reflinked += 1,
+                        LinkKind::Hardlink => report.hardlinked += 1,
+                    }
+                    report.files_linked += 1;
+                    report.bytes_saved += size;
+                }
+                Err(e) => report.errors.push(e.to_string()),
+            }
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,52 @@
+use crate::error::AppError;
+use crate::http::HttpClient;
+use std::time::Duration;
+
+fn history_name(binary_type: &str) -> Result<&'static str, AppError> {
+    match binary_type {
+        "WindowsPlayer" => Ok("WindowsPlayer"),
+        "WindowsStudio64" => Ok("Studio64"),
+        "MacPlayer" => Ok("Client"),
+        "MacStudio" => Ok("Studio"),
+        other => Err(AppError::validation(format!("Unknown binary type: {}", other))),
+    }
+}
+
+// DeployHistory.txt lists every deploy in order, one per line:
+// "New WindowsPlayer version-0123456789abcdef at 1/23/2023 5:18:53 PM, file version: ..."
+pub fn parse_history(text: &str, binary_type: &str) -> Result<Vec<String>, AppError> {
+    let name = history_name(binary_type)?;
+    let mut versions: Vec<String> = Vec::new();
+
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("New") || words.next() != Some(name) {
+            continue;
+        }
+
+        if let Some(version) = words.next().filter(|word| word.starts_with("version-")) {
+            let version = version.to_lowercase();
+            // Re-deploys of the same hash show up more than once, keep the first
+            if !versions.contains(&version) {
+                versions.push(version);
+            }
+        }
+    }
+
+    Ok(versions)
+}
+
+pub async fn fetch_history(
+    client: &dyn HttpClient,
+    base_url: &str,
+    blob_dir: &str,
+    binary_type: &str,
+) -> Result<Vec<String>, AppError> {
+    let url = format!("{}{}DeployHistory.txt", base_url, blob_dir);
+    let text = client.get(&url, Some(Duration::from_secs(30)))
+        .await?
+        .error_for_status("deploy history")?
+        .text()
+        .await?;
+    parse_history(&text, binary_type)
+}
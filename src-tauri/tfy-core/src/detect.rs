@@ -0,0 +1,274 @@
+use crate::catalog::CatalogApp;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+
+// Finds apps that are already on the machine, so the Apps page can say
+// "Installed" or "Update available" instead of fetching the installer again.
+// Windows lists them under the Uninstall registry keys, elsewhere a
+// DesktopDetector looks through .desktop files and known paths.
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InstalledApp {
+    // DisplayName, or Name= of a desktop file
+    pub name: String,
+    pub version: Option<String>,
+    pub publisher: Option<String>,
+    // Install folder, or the file the app was found through
+    pub location: Option<PathBuf>,
+}
+
+pub trait Detector: Send + Sync {
+    fn installed(&self) -> Result<Vec<InstalledApp>, AppError>;
+}
+
+// The registry on Windows, desktop files everywhere else
+pub fn default_detector() -> Box<dyn Detector> {
+    #[cfg(windows)]
+    {
+        Box::new(RegistryDetector)
+    }
+    #[cfg(not(windows))]
+    {
+        Box::new(DesktopDetector::system())
+    }
+}
+
+#[cfg(windows)]
+pub struct RegistryDetector;
+
+#[cfg(windows)]
+impl Detector for RegistryDetector {
+    fn installed(&self) -> Result<Vec<InstalledApp>, AppError> {
+        use winreg::enums::{HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+        use winreg::RegKey;
+
+        const UNINSTALL_KEYS: [(winreg::HKEY, &str); 3] = [
+            (HKEY_LOCAL_MACHINE, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
+            (HKEY_LOCAL_MACHINE, r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall"),
+            (HKEY_CURRENT_USER, r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall"),
+        ];
+
+        let mut installed = Vec::new();
+        for (root, path) in UNINSTALL_KEYS {
+            // The WOW6432Node key is missing on 32-bit Windows
+            let Ok(uninstall) = RegKey::predef(root).open_subkey(path) else {
+                continue;
+            };
+
+            for name in uninstall.enum_keys().filter_map(Result::ok) {
+                let Ok(entry) = uninstall.open_subkey(&name) else {
+                    continue;
+                };
+                let Ok(display_name) = entry.get_value::<String, _>("DisplayName") else {
+                    continue;
+                };
+                // Runtimes and updates that Programs and Features hides as well
+                if entry.get_value::<u32, _>("SystemComponent").unwrap_or(0) == 1 {
+                    continue;
+                }
+
+                let text = |value: &str| entry.get_value::<String, _>(value).ok().filter(|text| !text.trim().is_empty());
+                installed.push(InstalledApp {
+                    name: display_name.trim().to_string(),
+                    version: text("DisplayVersion"),
+                    publisher: text("Publisher"),
+                    location: text("InstallLocation").map(PathBuf::from),
+                });
+            }
+        }
+        Ok(installed)
+    }
+}
+
+// Desktop file ids of the Linux builds of catalog apps, from the distro package,
+// Flathub and the Snap Store, under the name the catalog matches. Found by id
+// so that a differently worded Name= still counts, e.g. "Visual Studio Code".
+// Apps that only exist for Windows are left out.
+const DESKTOP_FILES: [(&str, &[&str]); 7] = [
+    ("Spotify", &["spotify.desktop", "com.spotify.Client.desktop", "spotify_spotify.desktop"]),
+    ("Steam", &["steam.desktop", "com.valvesoftware.Steam.desktop", "steam_steam.desktop"]),
+    ("Discord", &["discord.desktop", "com.discordapp.Discord.desktop", "discord_discord.desktop"]),
+    ("Brave", &["brave-browser.desktop", "com.brave.Browser.desktop", "brave_brave.desktop"]),
+    ("VLC", &["vlc.desktop", "org.videolan.VLC.desktop", "vlc_vlc.desktop"]),
+    ("Stremio", &["smartcode-stremio.desktop", "com.stremio.Stremio.desktop"]),
+    ("Microsoft Visual Studio Code", &["code.desktop", "com.visualstudio.code.desktop", "code_code.desktop"]),
+];
+
+// Reads Name= and X-AppImage-Version= from the .desktop files in `dirs`, and
+// reports every `known` path that exists under its app name
+#[derive(Default)]
+pub struct DesktopDetector {
+    pub dirs: Vec<PathBuf>,
+    pub known: Vec<(String, PathBuf)>,
+}
+
+impl DesktopDetector {
+    // The user's and the system's application folders, Flatpak and Snap included
+    pub fn system() -> DesktopDetector {
+        let mut dirs: Vec<PathBuf> = dirs::data_dir().into_iter().collect();
+        let data_dirs = std::env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+        dirs.extend(data_dirs.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from));
+        dirs.extend(dirs::data_dir().map(|dir| dir.join("flatpak/exports/share")));
+        dirs.push(PathBuf::from("/var/lib/flatpak/exports/share"));
+        dirs.push(PathBuf::from("/var/lib/snapd/desktop"));
+
+        let dirs: Vec<PathBuf> = dirs.into_iter().map(|dir| dir.join("applications")).collect();
+        let known = DESKTOP_FILES.iter()
+            .flat_map(|(name, ids)| ids.iter().map(move |id| (name, id)))
+            .flat_map(|(name, id)| dirs.iter().map(move |dir| (name.to_string(), dir.join(id))))
+            .collect();
+        DesktopDetector { dirs, known }
+    }
+
+    // Counts `name` as installed whenever `path` exists
+    pub fn with_path(mut self, name: &str, path: impl Into<PathBuf>) -> DesktopDetector {
+        self.known.push((name.to_string(), path.into()));
+        self
+    }
+}
+
+impl Detector for DesktopDetector {
+    fn installed(&self) -> Result<Vec<InstalledApp>, AppError> {
+        let mut installed = Vec::new();
+
+        for dir in &self.dirs {
+            // Most of the default folders don't exist on any one machine
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let mut files: Vec<PathBuf> = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|extension| extension == "desktop"))
+                .collect();
+            files.sort();
+
+            for file in files {
+                if let Some(app) = fs::read_to_string(&file).ok().and_then(|text| parse_desktop_file(&text)) {
+                    installed.push(InstalledApp { location: Some(file), ..app });
+                }
+            }
+        }
+
+        for (name, path) in &self.known {
+            if path.exists() {
+                installed.push(InstalledApp {
+                    name: name.clone(),
+                    version: None,
+                    publisher: None,
+                    location: Some(path.clone()),
+                });
+            }
+        }
+        Ok(installed)
+    }
+}
+
+// Only the [Desktop Entry] group counts, and Hidden=true means the entry was deleted
+fn parse_desktop_file(text: &str) -> Option<InstalledApp> {
+    let mut in_entry = false;
+    let (mut name, mut version, mut hidden) = (None, None, false);
+
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry {
+            continue;
+        }
+        match line.split_once('=').map(|(key, value)| (key.trim(), value.trim())) {
+            Some(("Name", value)) => name = Some(value.to_string()),
+            Some(("X-AppImage-Version", value)) => version = Some(value.to_string()),
+            Some(("Hidden", value)) => hidden = value == "true",
+            _ => {}
+        }
+    }
+
+    if hidden {
+        return None;
+    }
+    Some(InstalledApp {
+        name: name.filter(|name| !name.is_empty())?,
+        version,
+        publisher: None,
+        location: None,
+    })
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AppStatus {
+    pub id: String,
+    pub installed: Option<InstalledApp>,
+    // True when the catalog pins a newer version than the installed one
+    pub update_available: bool,
+}
+
+// Whether `installed_name` is `wanted` or starts with it as whole words, so
+// that "Steam" finds "Steam" but not "Steamworks Common Redistributables"
+fn name_matches(installed_name: &str, wanted: &str) -> bool {
+    let installed_name = installed_name.to_lowercase();
+    let wanted = wanted.trim().to_lowercase();
+    match installed_name.strip_prefix(&wanted) {
+        Some(rest) => !wanted.is_empty() && !rest.starts_with(char::is_alphanumeric),
+        None => false,
+    }
+}
+
+// Dotted version numbers compared part by part, "1.10" is newer than "1.9" and
+// "1.2" equals "1.2.0". Only the first run of digits and dots counts, so
+// "v1.2 (64-bit)" is 1.2. None when either side has no number in it.
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let parts = |version: &str| -> Vec<u64> {
+        let start = version.find(|c: char| c.is_ascii_digit()).unwrap_or(version.len());
+        version[start..].split(|c: char| !c.is_ascii_digit() && c != '.').next().unwrap_or_default()
+            .split('.')
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return Some(ordering);
+        }
+    }
+    Some(Ordering::Equal)
+}
+
+// One status per catalog app. When several installed entries match, e.g. a
+// 32-bit and a 64-bit copy, the newest one is reported.
+pub fn statuses(apps: &[CatalogApp], installed: &[InstalledApp]) -> Vec<AppStatus> {
+    apps.iter()
+        .map(|app| {
+            let wanted = app.installed_name.as_deref().unwrap_or(&app.name);
+            let found = installed.iter()
+                .filter(|candidate| name_matches(&candidate.name, wanted))
+                .max_by(|a, b| match (&a.version, &b.version) {
+                    (Some(a), Some(b)) => compare_versions(a, b).unwrap_or(Ordering::Equal),
+                    (a, b) => a.is_some().cmp(&b.is_some()),
+                })
+                .cloned();
+
+            let update_available = match (&found, &app.version) {
+                (Some(InstalledApp { version: Some(installed), .. }), Some(latest)) => {
+                    compare_versions(latest, installed) == Some(Ordering::Greater)
+                }
+                _ => false,
+            };
+
+            AppStatus { id: app.id.clone(), installed: found, update_available }
+        })
+        .collect()
+}
+
+pub fn detect(apps: &[CatalogApp], detector: &dyn Detector) -> Result<Vec<AppStatus>, AppError> {
+    Ok(statuses(apps, &detector.installed()?))
+}
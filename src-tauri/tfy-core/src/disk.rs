@@ -0,0 +1,34 @@
+use crate::error::AppError;
+use std::path::Path;
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.2} {}", size, UNITS[unit])
+    }
+}
+
+// `dir` must already exist, it only picks the volume that gets checked
+pub fn ensure_free_space(dir: &Path, required: u64) -> Result<(), AppError> {
+    let available = fs2::available_space(dir)
+        .map_err(|e| AppError::io(format!("Failed to check free space on {}", dir.display()), e))?;
+
+    if required > available {
+        return Err(AppError::validation(format!(
+            "Not enough disk space on {}: {} needed, {} available",
+            dir.display(),
+            format_size(required),
+            format_size(available)
+        )));
+    }
+    Ok(())
+}
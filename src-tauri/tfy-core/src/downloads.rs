@@ -0,0 +1,634 @@
+use crate::apps::{self, DownloadRequest, DownloadedFile};
+use crate::error::AppError;
+use crate::http::SharedClient;
+use crate::registry;
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::{oneshot, Notify};
+
+// Every download goes through a DownloadManager: a queue that runs a few at a
+// time and a history in the app data directory that outlives restarts.
+// Downloads never start themselves, the UI runs a finished one through run()
+// after showing what the file is.
+//
+// Pausing stops the transfer and keeps the partial file, resuming asks the
+// server for the rest of it with a Range request.
+pub const DEFAULT_CONCURRENCY: usize = 2;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadStatus {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl DownloadStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, DownloadStatus::Completed | DownloadStatus::Failed | DownloadStatus::Cancelled)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DownloadRecord {
+    pub id: u64,
+    #[serde(flatten)]
+    pub request: DownloadRequest,
+    // Where the file ends up, it only exists there once the download completed
+    pub path: PathBuf,
+    pub status: DownloadStatus,
+    pub attempts: u32,
+    // Size, hash and executable details of the saved file
+    pub file: Option<DownloadedFile>,
+    // file.describe(), for the confirmation prompt
+    pub summary: Option<String>,
+    pub error: Option<String>,
+    pub queued_at: u64,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+}
+
+impl DownloadRecord {
+    // Next to where the request would save the file and named after the id, so
+    // two downloads of the same file never write to one partial file
+    pub fn partial_path(&self) -> PathBuf {
+        let mut partial = self.request.path().into_os_string();
+        partial.push(format!(".{}.part", self.id));
+        PathBuf::from(partial)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct DownloadHistory {
+    pub next_id: u64,
+    pub downloads: Vec<DownloadRecord>,
+}
+
+fn history_path() -> Result<PathBuf, AppError> {
+    let data_dir = dirs::data_dir()
+        .ok_or_else(|| AppError::not_found("Could not find app data directory"))?;
+    Ok(data_dir.join("tfy-tool").join("downloads.json"))
+}
+
+// The GUI and the CLI each have their own DownloadManager over the same
+// history file. Whoever reads or writes it holds a lock on downloads.json.lock
+// for as long as that takes, it is released when the file is closed.
+fn lock_history(exclusive: bool) -> Result<fs::File, AppError> {
+    let path = history_path()?.with_extension("json.lock");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io("Failed to create app data directory", e))?;
+    }
+
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| AppError::io("Failed to open the download history lock", e))?;
+    let locked = if exclusive { FileExt::lock_exclusive(&file) } else { FileExt::lock_shared(&file) };
+    locked.map_err(|e| AppError::io("Failed to lock the download history", e))?;
+    Ok(file)
+}
+
+// Held by the process transferring a download for as long as it is Running,
+// so restore() can tell a transfer that is still going from one whose process
+// has exited. The lock goes with the process however it ends.
+struct TransferLock {
+    file: fs::File,
+    path: PathBuf,
+}
+
+impl Drop for TransferLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// None when another transfer of download `id` holds it
+fn lock_transfer(id: u64) -> Result<Option<TransferLock>, AppError> {
+    let dir = history_path()?.with_file_name("downloads");
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::io("Failed to create app data directory", e))?;
+
+    let path = dir.join(format!("{}.lock", id));
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(|e| AppError::io(format!("Failed to open {}", path.display()), e))?;
+    match FileExt::try_lock_exclusive(&file) {
+        Ok(()) => Ok(Some(TransferLock { file, path })),
+        Err(_) => Ok(None),
+    }
+}
+
+fn read_history() -> Result<DownloadHistory, AppError> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(DownloadHistory::default());
+    }
+
+    let text = fs::read_to_string(&path)
+        .map_err(|e| AppError::io("Failed to read download history", e))?;
+    serde_json::from_str(&text)
+        .map_err(|e| AppError::validation(format!("Failed to parse download history: {}", e)))
+}
+
+pub fn load() -> Result<DownloadHistory, AppError> {
+    let _lock = lock_history(false)?;
+    read_history()
+}
+
+// Reads the history as it is on disk now, applies `change` and writes it back
+// before anyone else gets to it, so records and ids handed out by another
+// process in the meantime are kept. Returns the new history and what `change` did.
+fn modify<T, F>(change: F) -> Result<(DownloadHistory, T), AppError>
+where
+    F: FnOnce(&mut DownloadHistory) -> Result<T, AppError>,
+{
+    let _lock = lock_history(true)?;
+    // A damaged history file is started over rather than blocking every download
+    let mut history = read_history().unwrap_or_default();
+    let changed = change(&mut history)?;
+
+    let text = serde_json::to_string_pretty(&history)
+        .map_err(|e| AppError::io("Failed to serialize download history", e))?;
+    fs::write(history_path()?, text)
+        .map_err(|e| AppError::io("Failed to write download history", e))?;
+    Ok((history, changed))
+}
+
+impl DownloadHistory {
+    pub fn find(&self, id: u64) -> Result<&DownloadRecord, AppError> {
+        self.downloads.iter()
+            .find(|download| download.id == id)
+            .ok_or_else(|| AppError::not_found(format!("Unknown download: {}", id)))
+    }
+
+    fn find_mut(&mut self, id: u64) -> Result<&mut DownloadRecord, AppError> {
+        self.downloads.iter_mut()
+            .find(|download| download.id == id)
+            .ok_or_else(|| AppError::not_found(format!("Unknown download: {}", id)))
+    }
+}
+
+enum Stop {
+    Pause,
+    Cancel,
+}
+
+struct State {
+    history: DownloadHistory,
+    // Queued downloads this manager may start: the ones it queued itself or
+    // restore()d. The rest of the history belongs to another process.
+    owned: HashSet<u64>,
+    // Stop signals of the running downloads, taken once sent
+    running: HashMap<u64, Option<oneshot::Sender<Stop>>>,
+    // The full error of the last failure, handed to whoever wait()s on it
+    failures: HashMap<u64, AppError>,
+}
+
+type Listener = Arc<dyn Fn(&DownloadRecord) + Send + Sync>;
+
+struct Shared {
+    client: SharedClient,
+    limit: usize,
+    state: Mutex<State>,
+    changed: Notify,
+    listener: Mutex<Option<Listener>>,
+}
+
+#[derive(Clone)]
+pub struct DownloadManager {
+    shared: Arc<Shared>,
+}
+
+impl DownloadManager {
+    // Loads the history without starting anything in it, see restore()
+    pub fn new(client: SharedClient, limit: usize) -> DownloadManager {
+        // A damaged history file is started over rather than blocking every download
+        let history = load().unwrap_or_default();
+
+        DownloadManager {
+            shared: Arc::new(Shared {
+                client,
+                limit: limit.max(1),
+                state: Mutex::new(State { history, owned: HashSet::new(), running: HashMap::new(), failures: HashMap::new() }),
+                changed: Notify::new(),
+                listener: Mutex::new(None),
+            }),
+        }
+    }
+
+    // Called with every record that changes, e.g. to forward it to the UI
+    pub fn on_change<F>(&self, listener: F)
+    where
+        F: Fn(&DownloadRecord) + Send + Sync + 'static,
+    {
+        *self.shared.listener.lock().unwrap() = Some(Arc::new(listener));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.shared.state.lock().unwrap()
+    }
+
+    // Picks up what other processes wrote to the history since this one last did
+    fn refresh(&self) -> MutexGuard<'_, State> {
+        let mut state = self.lock();
+        if let Ok(history) = load() {
+            state.history = history;
+        }
+        state
+    }
+
+    fn changed(&self, download: &DownloadRecord) {
+        if let Some(listener) = self.shared.listener.lock().unwrap().clone() {
+            listener(download);
+        }
+        self.shared.changed.notify_waiters();
+    }
+
+    // Applies `change` to one record, saves the history and tells everyone
+    fn update<F>(&self, state: &mut State, id: u64, change: F) -> Result<DownloadRecord, AppError>
+    where
+        F: FnOnce(&mut DownloadRecord) -> Result<(), AppError>,
+    {
+        let (history, download) = modify(|history| {
+            let download = history.find_mut(id)?;
+            change(download)?;
+            Ok(download.clone())
+        })?;
+
+        state.history = history;
+        self.changed(&download);
+        Ok(download)
+    }
+
+    // Adds the record `new` builds for the next free id
+    fn add<F>(&self, state: &mut State, new: F) -> Result<DownloadRecord, AppError>
+    where
+        F: FnOnce(u64) -> DownloadRecord,
+    {
+        let (history, download) = modify(|history| {
+            history.next_id += 1;
+            let download = new(history.next_id);
+            history.downloads.push(download.clone());
+            Ok(download)
+        })?;
+
+        state.history = history;
+        self.changed(&download);
+        Ok(download)
+    }
+
+    pub fn list(&self) -> Vec<DownloadRecord> {
+        self.refresh().history.downloads.clone()
+    }
+
+    pub fn get(&self, id: u64) -> Result<DownloadRecord, AppError> {
+        self.refresh().history.find(id).cloned()
+    }
+
+    // Queues the downloads that were running when the app last closed again and
+    // starts them with whatever else was waiting. The GUI does this once at
+    // startup, the CLI leaves downloads it didn't queue alone. Downloads still
+    // running in another process, e.g. the CLI, are left to it.
+    pub fn restore(&self) -> Result<usize, AppError> {
+        let restored = {
+            let mut state = self.lock();
+            let (history, restored) = modify(|history| {
+                let mut restored = Vec::new();
+                for download in &mut history.downloads {
+                    // A transfer lock nobody holds belonged to a process that is gone
+                    let abandoned = download.status == DownloadStatus::Running
+                        && matches!(lock_transfer(download.id), Ok(Some(_)));
+                    if abandoned {
+                        apps::remove_partial(&download.partial_path());
+                        download.status = DownloadStatus::Queued;
+                    }
+                    if download.status == DownloadStatus::Queued {
+                        restored.push(download.id);
+                    }
+                }
+                Ok(restored)
+            })?;
+            state.history = history;
+            state.owned.extend(&restored);
+            restored.len()
+        };
+
+        self.start();
+        Ok(restored)
+    }
+
+    // Must be called from inside a tokio runtime, like everything that starts downloads
+    pub fn start(&self) {
+        let mut state = self.lock();
+        let queued: Vec<u64> = state.history.downloads.iter()
+            .filter(|download| download.status == DownloadStatus::Queued && state.owned.contains(&download.id))
+            .map(|download| download.id)
+            .collect();
+
+        for next in queued {
+            if state.running.len() >= self.shared.limit {
+                break;
+            }
+
+            // Skipped when another process got to it first or the history
+            // can't be written, the next start() tries again
+            let Ok(Some(owner)) = lock_transfer(next) else {
+                continue;
+            };
+            let Ok(download) = self.update(&mut state, next, |download| {
+                if download.status != DownloadStatus::Queued {
+                    return Err(AppError::validation(format!("Download {} is no longer queued", next)));
+                }
+                download.status = DownloadStatus::Running;
+                download.attempts += 1;
+                download.started_at = Some(registry::now());
+                download.finished_at = None;
+                download.error = None;
+                Ok(())
+            }) else {
+                continue;
+            };
+
+            let (stop, stopped) = oneshot::channel();
+            state.running.insert(next, Some(stop));
+            tokio::spawn(self.clone().transfer(download, stopped, owner));
+        }
+    }
+
+    // `owner` is released once the record no longer says Running
+    async fn transfer(self, download: DownloadRecord, stopped: oneshot::Receiver<Stop>, owner: TransferLock) {
+        let client = Arc::clone(&self.shared.client);
+        let partial = download.partial_path();
+        let outcome = tokio::select! {
+            result = apps::resume(client.as_ref(), &download.request, &partial) => Ok(result),
+            Ok(stop) = stopped => Err(stop),
+        };
+
+        // A stopped transfer is dropped mid-write and leaves its partial file
+        // behind, only a paused one is picked up from there
+        if !matches!(outcome, Ok(Ok(_)) | Err(Stop::Pause)) {
+            apps::remove_partial(&partial);
+        }
+
+        let (status, file, failure) = match outcome {
+            Ok(Ok(file)) => (DownloadStatus::Completed, Some(file), None),
+            Ok(Err(e)) => (DownloadStatus::Failed, None, Some(e)),
+            Err(Stop::Pause) => (DownloadStatus::Paused, None, None),
+            Err(Stop::Cancel) => (DownloadStatus::Cancelled, None, None),
+        };
+        let error = failure.as_ref().map(AppError::to_string);
+        let finish = |record: &mut DownloadRecord| {
+            record.status = status;
+            if let Some(file) = &file {
+                // The response may have named the file differently
+                record.path = file.path.clone();
+                record.summary = Some(file.describe());
+                record.file = Some(file.clone());
+            }
+            record.error = error.clone();
+            if status.is_finished() {
+                record.finished_at = Some(registry::now());
+            }
+        };
+
+        {
+            let mut state = self.lock();
+            state.running.remove(&download.id);
+            if let Some(failure) = failure {
+                state.failures.insert(download.id, failure);
+            }
+            let saved = self.update(&mut state, download.id, |record| {
+                finish(record);
+                Ok(())
+            });
+            // wait() still has to hear about it when the history can't be written
+            if saved.is_err() {
+                if let Ok(record) = state.history.find_mut(download.id) {
+                    finish(record);
+                }
+                self.shared.changed.notify_waiters();
+            }
+        }
+
+        drop(owner);
+        self.start();
+    }
+
+    pub fn enqueue(&self, request: DownloadRequest) -> Result<DownloadRecord, AppError> {
+        let mut state = self.lock();
+        let download = self.add(&mut state, |id| DownloadRecord {
+            id,
+            path: request.path(),
+            request,
+            status: DownloadStatus::Queued,
+            attempts: 0,
+            file: None,
+            summary: None,
+            error: None,
+            queued_at: registry::now(),
+            started_at: None,
+            finished_at: None,
+        })?;
+        state.owned.insert(download.id);
+        drop(state);
+
+        self.start();
+        Ok(download)
+    }
+
+    // For files that arrive some other way, e.g. unpacked from an archive, so
+    // they can still go through run()
+    pub fn add_completed(&self, name: &str, url: &str, file: DownloadedFile) -> Result<DownloadRecord, AppError> {
+        let now = registry::now();
+        self.add(&mut self.lock(), |id| DownloadRecord {
+            id,
+            request: DownloadRequest {
+                name: name.to_string(),
+                url: url.to_string(),
+                dir: file.path.parent().map(PathBuf::from).unwrap_or_default(),
+                filename: file.path.file_name().map(|name| name.to_string_lossy().into_owned()),
+                sha256: None,
+                claimed_publisher: None,
+            },
+            path: file.path.clone(),
+            status: DownloadStatus::Completed,
+            attempts: 1,
+            summary: Some(file.describe()),
+            file: Some(file),
+            error: None,
+            queued_at: now,
+            started_at: Some(now),
+            finished_at: Some(now),
+        })
+    }
+
+    // Resolves once the download has completed, with its error if it failed or was cancelled
+    pub async fn wait(&self, id: u64) -> Result<DownloadRecord, AppError> {
+        loop {
+            // Registered before looking, so a change in between is not missed
+            let changed = self.shared.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            {
+                let mut state = self.lock();
+                let download = state.history.find(id)?.clone();
+                match download.status {
+                    DownloadStatus::Completed => return Ok(download),
+                    DownloadStatus::Failed => {
+                        return Err(state.failures.remove(&id).unwrap_or_else(|| {
+                            AppError::validation(download.error.unwrap_or_else(|| "Download failed".to_string()))
+                        }));
+                    }
+                    DownloadStatus::Cancelled => {
+                        return Err(AppError::cancelled(format!("Download of {} was cancelled", download.request.name)));
+                    }
+                    _ => {}
+                }
+            }
+
+            changed.await;
+        }
+    }
+
+    pub fn pause(&self, id: u64) -> Result<DownloadRecord, AppError> {
+        self.stop(id, Stop::Pause, DownloadStatus::Paused)
+    }
+
+    pub fn cancel(&self, id: u64) -> Result<DownloadRecord, AppError> {
+        self.stop(id, Stop::Cancel, DownloadStatus::Cancelled)
+    }
+
+    // Waiting downloads change status here, running ones once their transfer has stopped
+    fn stop(&self, id: u64, stop: Stop, status: DownloadStatus) -> Result<DownloadRecord, AppError> {
+        let mut state = self.lock();
+        if let Some(signal) = state.running.get_mut(&id) {
+            if let Some(sender) = signal.take() {
+                let _ = sender.send(stop);
+            }
+            return state.history.find(id).cloned();
+        }
+
+        let download = self.update(&mut state, id, |download| {
+            match download.status {
+                DownloadStatus::Queued | DownloadStatus::Paused => {
+                    download.status = status;
+                    if status.is_finished() {
+                        download.finished_at = Some(registry::now());
+                    }
+                    Ok(())
+                }
+                other => Err(AppError::validation(format!(
+                    "Download {} is {:?} and can't be stopped",
+                    id, other
+                ))),
+            }
+        })?;
+
+        // What a paused download had so far goes with it
+        if download.status == DownloadStatus::Cancelled {
+            apps::remove_partial(&download.partial_path());
+        }
+        Ok(download)
+    }
+
+    pub fn resume(&self, id: u64) -> Result<DownloadRecord, AppError> {
+        self.requeue(id, &[DownloadStatus::Paused])
+    }
+
+    pub fn retry(&self, id: u64) -> Result<DownloadRecord, AppError> {
+        self.requeue(id, &[DownloadStatus::Failed, DownloadStatus::Cancelled])
+    }
+
+    fn requeue(&self, id: u64, from: &[DownloadStatus]) -> Result<DownloadRecord, AppError> {
+        let download = {
+            let mut state = self.lock();
+            state.failures.remove(&id);
+            let download = self.update(&mut state, id, |download| {
+                if !from.contains(&download.status) {
+                    return Err(AppError::validation(format!(
+                        "Download {} is {:?}, only {:?} downloads can be started again",
+                        id, download.status, from
+                    )));
+                }
+                download.status = DownloadStatus::Queued;
+                download.finished_at = None;
+                download.error = None;
+                download.file = None;
+                download.summary = None;
+                Ok(())
+            })?;
+            state.owned.insert(id);
+            download
+        };
+
+        self.start();
+        Ok(download)
+    }
+
+    // Drops finished downloads from the history, not their files. Returns how many went.
+    pub fn clear_history(&self) -> Result<usize, AppError> {
+        let mut state = self.lock();
+        let (history, removed) = modify(|history| {
+            let before = history.downloads.len();
+            history.downloads.retain(|download| !download.status.is_finished());
+            Ok(before - history.downloads.len())
+        })?;
+
+        state.failures.retain(|id, _| history.find(*id).is_ok());
+        state.history = history;
+        self.shared.changed.notify_waiters();
+        Ok(removed)
+    }
+
+    // A completed download whose file is hashed again and still matches, so
+    // whatever the user confirmed is exactly what runs
+    pub fn verified_file(&self, id: u64) -> Result<(DownloadRecord, DownloadedFile), AppError> {
+        let download = self.get(id)?;
+        let file = match (&download.file, download.status) {
+            (Some(file), DownloadStatus::Completed) => file.clone(),
+            _ => {
+                return Err(AppError::validation(format!(
+                    "{} has not finished downloading",
+                    download.request.name
+                )));
+            }
+        };
+
+        if !file.path.is_file() {
+            return Err(AppError::not_found(format!("{} is no longer at {}", download.request.name, file.path.display())));
+        }
+        let sha256 = apps::hash_file(&file.path)?;
+        if sha256 != file.sha256 {
+            return Err(AppError::validation(format!(
+                "{} changed since it was downloaded: expected {}, got {}. It was not run.",
+                file.path.display(),
+                file.sha256,
+                sha256
+            )));
+        }
+
+        Ok((download, file))
+    }
+
+    // Starts a completed download after verified_file()
+    pub fn run(&self, id: u64) -> Result<DownloadRecord, AppError> {
+        let (download, file) = self.verified_file(id)?;
+        apps::open_file(&file.path)?;
+        Ok(download)
+    }
+}
@@ -0,0 +1,104 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+
+// Every command fails with one of these. The frontend receives
+// `{ kind, message, details }` and can branch on `kind`.
+#[derive(Debug)]
+pub enum AppError {
+    Network { message: String, details: String },
+    HttpStatus { status: u16, message: String },
+    Io { message: String, details: String },
+    Archive { message: String, details: String },
+    Validation(String),
+    NotFound(String),
+    Cancelled(String),
+}
+
+impl AppError {
+    pub fn network(message: impl Into<String>, error: impl fmt::Display) -> AppError {
+        AppError::Network { message: message.into(), details: error.to_string() }
+    }
+
+    pub fn http_status(status: u16, message: impl Into<String>) -> AppError {
+        AppError::HttpStatus { status, message: message.into() }
+    }
+
+    pub fn io(message: impl Into<String>, error: impl fmt::Display) -> AppError {
+        AppError::Io { message: message.into(), details: error.to_string() }
+    }
+
+    pub fn archive(message: impl Into<String>, error: impl fmt::Display) -> AppError {
+        AppError::Archive { message: message.into(), details: error.to_string() }
+    }
+
+    pub fn validation(message: impl Into<String>) -> AppError {
+        AppError::Validation(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> AppError {
+        AppError::NotFound(message.into())
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> AppError {
+        AppError::Cancelled(message.into())
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Network { .. } => "network",
+            AppError::HttpStatus { .. } => "http_status",
+            AppError::Io { .. } => "io",
+            AppError::Archive { .. } => "archive",
+            AppError::Validation(_) => "validation",
+            AppError::NotFound(_) => "not_found",
+            AppError::Cancelled(_) => "cancelled",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Network { message, .. }
+            | AppError::HttpStatus { message, .. }
+            | AppError::Io { message, .. }
+            | AppError::Archive { message, .. }
+            | AppError::Validation(message)
+            | AppError::NotFound(message)
+            | AppError::Cancelled(message) => message,
+        }
+    }
+
+    pub fn details(&self) -> Value {
+        match self {
+            AppError::Network { details, .. }
+            | AppError::Io { details, .. }
+            | AppError::Archive { details, .. } => Value::String(details.clone()),
+            AppError::HttpStatus { status, .. } => json!({ "status": status }),
+            AppError::Validation(_) | AppError::NotFound(_) | AppError::Cancelled(_) => Value::Null,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Network { message, details }
+            | AppError::Io { message, details }
+            | AppError::Archive { message, details } => write!(f, "{}: {}", message, details),
+            AppError::HttpStatus { status, message } => write!(f, "{}: HTTP {}", message, status),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", self.message())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
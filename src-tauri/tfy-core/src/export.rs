@@ -0,0 +1,142 @@
+use crate::error::AppError;
+use crate::registry::InstallRecord;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+// Archive layout: "tfy-export.json" at the root, the version folder under "files/"
+const METADATA_NAME: &str = "tfy-export.json";
+const FILES_PREFIX: &str = "files/";
+const EXPORT_FORMAT: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportMetadata {
+    pub format: u32,
+    pub version: String,
+    pub channel: String,
+    pub binary_type: String,
+    pub installed_at: u64,
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| AppError::io(format!("Failed to read directory {}", dir.display()), e))?;
+
+    for entry in entries {
+        let path = entry
+            .map_err(|e| AppError::io(format!("Failed to read directory {}", dir.display()), e))?
+            .path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+pub fn export_install(record: &InstallRecord, archive_path: &Path) -> Result<usize, AppError> {
+    let mut files = Vec::new();
+    collect_files(&record.path, &mut files)?;
+
+    if let Some(parent) = archive_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io("Failed to create export directory", e))?;
+    }
+
+    let archive_file = fs::File::create(archive_path)
+        .map_err(|e| AppError::io(format!("Failed to create archive {}", archive_path.display()), e))?;
+    let mut writer = ZipWriter::new(archive_file);
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    let metadata = ExportMetadata {
+        format: EXPORT_FORMAT,
+        version: record.version.clone(),
+        channel: record.channel.clone(),
+        binary_type: record.binary_type.clone(),
+        installed_at: record.installed_at,
+    };
+    let metadata_json = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| AppError::io("Failed to serialize export metadata", e))?;
+
+    writer.start_file(METADATA_NAME, options)
+        .map_err(|e| AppError::archive("Failed to write archive", e))?;
+    writer.write_all(metadata_json.as_bytes())
+        .map_err(|e| AppError::archive("Failed to write archive", e))?;
+
+    for file_path in &files {
+        let relative = file_path.strip_prefix(&record.path)
+            .map_err(|e| AppError::io(format!("Failed to resolve {}", file_path.display()), e))?;
+        let name = format!("{}{}", FILES_PREFIX, relative.to_string_lossy().replace('\\', "/"));
+
+        writer.start_file(name, options)
+            .map_err(|e| AppError::archive("Failed to write archive", e))?;
+        let mut input = fs::File::open(file_path)
+            .map_err(|e| AppError::io(format!("Failed to open {}", file_path.display()), e))?;
+        std::io::copy(&mut input, &mut writer)
+            .map_err(|e| AppError::archive(format!("Failed to pack {}", file_path.display()), e))?;
+    }
+
+    writer.finish()
+        .map_err(|e| AppError::archive("Failed to finish archive", e))?;
+    Ok(files.len())
+}
+
+pub fn read_metadata(archive_path: &Path) -> Result<ExportMetadata, AppError> {
+    let archive_file = fs::File::open(archive_path)
+        .map_err(|e| AppError::io(format!("Failed to open archive {}", archive_path.display()), e))?;
+    let mut archive = ZipArchive::new(archive_file)
+        .map_err(|e| AppError::archive(format!("Failed to read archive {}", archive_path.display()), e))?;
+
+    let metadata_file = archive.by_name(METADATA_NAME)
+        .map_err(|_| AppError::validation(format!("{} is not a TFY version export", archive_path.display())))?;
+    let metadata: ExportMetadata = serde_json::from_reader(metadata_file)
+        .map_err(|e| AppError::validation(format!("Failed to parse export metadata: {}", e)))?;
+
+    if metadata.format != EXPORT_FORMAT {
+        return Err(AppError::validation(format!("Unsupported export format: {}", metadata.format)));
+    }
+    Ok(metadata)
+}
+
+pub fn import_files(archive_path: &Path, output_dir: &Path) -> Result<usize, AppError> {
+    let archive_file = fs::File::open(archive_path)
+        .map_err(|e| AppError::io(format!("Failed to open archive {}", archive_path.display()), e))?;
+    let mut archive = ZipArchive::new(archive_file)
+        .map_err(|e| AppError::archive(format!("Failed to read archive {}", archive_path.display()), e))?;
+
+    let mut count = 0;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)
+            .map_err(|e| AppError::archive("Failed to read file from archive", e))?;
+
+        if file.is_dir() {
+            continue;
+        }
+
+        // enclosed_name rejects absolute paths and ".." components
+        let relative = match file.enclosed_name().and_then(|name| name.strip_prefix(FILES_PREFIX).ok()) {
+            Some(relative) => relative.to_path_buf(),
+            None => continue,
+        };
+
+        let file_path = output_dir.join(relative);
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::io(format!("Failed to create directory {}", parent.display()), e))?;
+        }
+
+        let mut output_file = fs::File::create(&file_path)
+            .map_err(|e| AppError::io(format!("Failed to create file {}", file_path.display()), e))?;
+        std::io::copy(&mut file, &mut output_file)
+            .map_err(|e| AppError::archive(format!("Failed to extract file {}", file_path.display()), e))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
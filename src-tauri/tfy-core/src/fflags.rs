@@ -0,0 +1,173 @@
+use crate::error::AppError;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub type FlagSet = Map<String, Value>;
+
+fn flags_path(install_dir: &Path) -> PathBuf {
+    install_dir.join("ClientSettings").join("ClientAppSettings.json")
+}
+
+fn presets_path() -> Result<PathBuf, AppError> {
+    let config_dir = dirs::config_dir()
+        .ok_or_else(|| AppError::not_found("Could not find config directory"))?;
+    Ok(config_dir.join("tfy-tool").join("fflag-presets.json"))
+}
+
+// Fast flags are plain name/value pairs, nested objects and arrays are never valid
+pub fn validate_flags(flags: &FlagSet) -> Result<(), AppError> {
+    for (name, value) in flags {
+        if name.trim().is_empty() {
+            return Err(AppError::validation("Flag names cannot be empty"));
+        }
+        if !(value.is_string() || value.is_number() || value.is_boolean()) {
+            return Err(AppError::validation(format!("Flag {} must be a string, number or boolean", name)));
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_flags(text: &str) -> Result<FlagSet, AppError> {
+    let value: Value = serde_json::from_str(text)
+        .map_err(|e| AppError::validation(format!("Invalid flag JSON: {}", e)))?;
+    let Value::Object(flags) = value else {
+        return Err(AppError::validation("Flag JSON must be an object of name/value pairs"));
+    };
+
+    validate_flags(&flags)?;
+    Ok(flags)
+}
+
+pub fn read_flags(install_dir: &Path) -> Result<FlagSet, AppError> {
+    let path = flags_path(install_dir);
+    if !path.exists() {
+        return Ok(FlagSet::new());
+    }
+
+    let text = fs::read_to_string(&path)
+        .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?;
+    parse_flags(&text)
+}
+
+// The previous file is kept as ClientAppSettings.json.bak
+pub fn write_flags(install_dir: &Path, flags: &FlagSet) -> Result<(), AppError> {
+    validate_flags(flags)?;
+
+    let path = flags_path(install_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io(format!("Failed to create {}", parent.display()), e))?;
+    }
+
+    if path.exists() {
+        fs::copy(&path, path.with_extension("json.bak"))
+            .map_err(|e| AppError::io(format!("Failed to back up {}", path.display()), e))?;
+    }
+
+    let text = serde_json::to_string_pretty(flags)
+        .map_err(|e| AppError::io("Failed to serialize flags", e))?;
+    fs::write(&path, text)
+        .map_err(|e| AppError::io(format!("Failed to write {}", path.display()), e))
+}
+
+pub fn merge_flags(flags: &mut FlagSet, incoming: FlagSet, replace: bool) {
+    if replace {
+        flags.clear();
+    }
+    flags.extend(incoming);
+}
+
+pub fn load_presets() -> Result<BTreeMap<String, FlagSet>, AppError> {
+    let path = presets_path()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let text = fs::read_to_string(&path)
+        .map_err(|e| AppError::io("Failed to read flag presets", e))?;
+    serde_json::from_str(&text)
+        .map_err(|e| AppError::validation(format!("Failed to parse flag presets: {}", e)))
+}
+
+pub fn save_presets(presets: &BTreeMap<String, FlagSet>) -> Result<(), AppError> {
+    let path = presets_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io("Failed to create config directory", e))?;
+    }
+
+    let text = serde_json::to_string_pretty(presets)
+        .map_err(|e| AppError::io("Failed to serialize flag presets", e))?;
+    fs::write(&path, text)
+        .map_err(|e| AppError::io("Failed to write flag presets", e))
+}
+
+pub fn set_flag(install_dir: &Path, name: String, value: Value) -> Result<FlagSet, AppError> {
+    let mut flags = read_flags(install_dir)?;
+    flags.insert(name, value);
+    write_flags(install_dir, &flags)?;
+    Ok(flags)
+}
+
+pub fn remove_flag(install_dir: &Path, name: &str) -> Result<FlagSet, AppError> {
+    let mut flags = read_flags(install_dir)?;
+    if flags.remove(name).is_none() {
+        return Err(AppError::not_found(format!("Flag {} is not set", name)));
+    }
+    write_flags(install_dir, &flags)?;
+    Ok(flags)
+}
+
+pub fn save_preset(name: String, flags: FlagSet) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::validation("Preset name cannot be empty"));
+    }
+    validate_flags(&flags)?;
+
+    let mut presets = load_presets()?;
+    presets.insert(name, flags);
+    save_presets(&presets)
+}
+
+pub fn delete_preset(name: &str) -> Result<(), AppError> {
+    let mut presets = load_presets()?;
+    if presets.remove(name).is_none() {
+        return Err(AppError::not_found(format!("Unknown flag preset: {}", name)));
+    }
+    save_presets(&presets)
+}
+
+pub fn apply_preset(install_dir: &Path, preset: &str, replace: bool) -> Result<FlagSet, AppError> {
+    let preset_flags = load_presets()?
+        .remove(preset)
+        .ok_or_else(|| AppError::not_found(format!("Unknown flag preset: {}", preset)))?;
+
+    let mut flags = read_flags(install_dir)?;
+    merge_flags(&mut flags, preset_flags, replace);
+    write_flags(install_dir, &flags)?;
+    Ok(flags)
+}
+
+pub fn import_file(install_dir: &Path, path: &Path, replace: bool) -> Result<FlagSet, AppError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?;
+    let incoming = parse_flags(&text)?;
+
+    let mut flags = read_flags(install_dir)?;
+    merge_flags(&mut flags, incoming, replace);
+    write_flags(install_dir, &flags)?;
+    Ok(flags)
+}
+
+// Returns the number of flags written
+pub fn export_file(install_dir: &Path, path: &Path) -> Result<usize, AppError> {
+    let flags = read_flags(install_dir)?;
+
+    let text = serde_json::to_string_pretty(&flags)
+        .map_err(|e| AppError::io("Failed to serialize flags", e))?;
+    fs::write(path, text)
+        .map_err(|e| AppError::io(format!("Failed to write {}", path.display()), e))?;
+    Ok(flags.len())
+}
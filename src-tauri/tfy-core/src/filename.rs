@@ -0,0 +1,250 @@
+// Picks the name a download is saved under. Vendor links like
+// discord.com/api/download?platform=win only say what they are in the final
+// response, so the name comes from Content-Disposition, then the redirected
+// URL, then whatever the caller suggested, and the extension is corrected from
+// the first bytes of the file. Every candidate is sanitised before it gets
+// anywhere near a path.
+
+// Long enough for any real installer name, short enough to stay clear of MAX_PATH
+const MAX_LENGTH: usize = 150;
+
+const RESERVED_NAMES: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    // MZ header, an .exe
+    Pe,
+    // Compound File Binary, which is what a Windows Installer package is
+    Msi,
+    Zip,
+}
+
+impl FileKind {
+    pub fn extension(self) -> &'static str {
+        match self {
+            FileKind::Pe => "exe",
+            FileKind::Msi => "msi",
+            FileKind::Zip => "zip",
+        }
+    }
+
+    // Extensions that are already right for this kind of file and are kept as they are
+    fn accepts(self, extension: &str) -> bool {
+        let accepted: &[&str] = match self {
+            FileKind::Pe => &["exe"],
+            FileKind::Msi => &["msi", "msp"],
+            FileKind::Zip => &["zip", "msix", "msixbundle", "appx", "appxbundle"],
+        };
+        accepted.iter().any(|accepted| accepted.eq_ignore_ascii_case(extension))
+    }
+}
+
+// Looks at the first bytes of a file, 8 are enough
+pub fn sniff(bytes: &[u8]) -> Option<FileKind> {
+    if bytes.starts_with(b"MZ") {
+        Some(FileKind::Pe)
+    } else if bytes.starts_with(&[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1]) {
+        Some(FileKind::Msi)
+    } else if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+        Some(FileKind::Zip)
+    } else {
+        None
+    }
+}
+
+// Turns `name` into a plain file name that is safe to join onto a directory on
+// Windows: no path, no characters Explorer refuses, no trailing dots and no
+// device names like CON. None when nothing usable is left.
+pub fn sanitize(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = name.chars()
+        .filter(|c| !c.is_control() && !matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*'))
+        .collect();
+    let name = name.trim().trim_end_matches(['.', ' ']).trim_start_matches(['.', ' ']);
+    if name.is_empty() {
+        return None;
+    }
+
+    let (stem, extension) = split_extension(name);
+    let stem: String = stem.chars().take(MAX_LENGTH).collect();
+    let mut name = match extension {
+        Some(extension) => format!("{}.{}", stem.trim_end(), extension),
+        None => stem,
+    };
+
+    // Windows ignores the extension here, con.exe is still the console
+    let device = name.split('.').next().unwrap_or_default().trim_end().to_ascii_uppercase();
+    let numbered = (device.starts_with("COM") || device.starts_with("LPT"))
+        && device.len() == 4
+        && device.as_bytes()[3].is_ascii_digit();
+    if RESERVED_NAMES.contains(&device.as_str()) || numbered {
+        name.insert(0, '_');
+    }
+    Some(name)
+}
+
+fn split_extension(name: &str) -> (&str, Option<&str>) {
+    match name.rsplit_once('.') {
+        // "1.0.9" in tool-1.0.9 is a version, not an extension
+        Some((stem, extension))
+            if !stem.is_empty()
+                && extension.len() <= 10
+                && extension.chars().all(|c| c.is_ascii_alphanumeric())
+                && extension.chars().any(|c| c.is_ascii_alphabetic()) =>
+        {
+            (stem, Some(extension))
+        }
+        _ => (name, None),
+    }
+}
+
+pub fn extension(name: &str) -> Option<&str> {
+    split_extension(name).1
+}
+
+// "setup (2).exe" for `n` = 2, the way browsers name a second copy
+pub fn numbered(name: &str, n: u32) -> String {
+    match split_extension(name) {
+        (stem, Some(extension)) => format!("{} ({}).{}", stem, n, extension),
+        (stem, None) => format!("{} ({})", stem, n),
+    }
+}
+
+// The filename parameter of a Content-Disposition header, preferring the
+// RFC 5987 filename* form. Not sanitised yet.
+pub fn from_content_disposition(header: &str) -> Option<String> {
+    let mut plain = None;
+    let mut extended = None;
+
+    for (key, value) in parameters(header) {
+        match key.as_str() {
+            "filename" if !value.is_empty() => plain = Some(value),
+            "filename*" => extended = decode_extended(&value).or(extended),
+            _ => {}
+        }
+    }
+    extended.or(plain)
+}
+
+// The key=value pairs after the disposition type, with quoted values unescaped
+fn parameters(header: &str) -> Vec<(String, String)> {
+    let mut parameters = Vec::new();
+    let mut chars = header.chars().peekable();
+
+    // Skip "attachment" or "inline"
+    for c in chars.by_ref() {
+        if c == ';' {
+            break;
+        }
+    }
+
+    loop {
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && *c != ';') {
+            key.push(c);
+        }
+        let key = key.trim().to_ascii_lowercase();
+        match chars.next() {
+            Some('=') => {}
+            // A bare word without a value
+            Some(_) => continue,
+            None => break,
+        }
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    c => value.push(c),
+                }
+            }
+            // Anything between the closing quote and the next ; is junk
+            for c in chars.by_ref() {
+                if c == ';' {
+                    break;
+                }
+            }
+        } else {
+            value = chars.by_ref().take_while(|c| *c != ';').collect();
+            value = value.trim().to_string();
+        }
+        parameters.push((key, value));
+    }
+    parameters
+}
+
+// charset'language'percent-encoded-bytes
+fn decode_extended(value: &str) -> Option<String> {
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?.trim();
+    let _language = parts.next()?;
+    let bytes = percent_decode(parts.next()?);
+
+    let decoded = if charset.eq_ignore_ascii_case("utf-8") {
+        String::from_utf8(bytes).ok()?
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        bytes.into_iter().map(char::from).collect()
+    } else {
+        return None;
+    };
+    Some(decoded).filter(|decoded| !decoded.is_empty())
+}
+
+fn percent_decode(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+// The last path segment of `url`, decoded. Not sanitised yet.
+pub fn from_url(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let segment = url.path_segments()?.next_back()?;
+    let decoded = String::from_utf8(percent_decode(segment)).ok()?;
+    Some(decoded).filter(|decoded| !decoded.is_empty())
+}
+
+// The name to save a response under. A name from the final URL only counts
+// when it has an extension, otherwise "download" from .../api/download would
+// beat the caller's "Discord". `fallback` is used when the server says nothing.
+pub fn resolve(content_disposition: Option<&str>, final_url: &str, fallback: &str) -> String {
+    content_disposition
+        .and_then(from_content_disposition)
+        .and_then(|name| sanitize(&name))
+        .or_else(|| from_url(final_url).and_then(|name| sanitize(&name)).filter(|name| extension(name).is_some()))
+        .or_else(|| sanitize(fallback))
+        .unwrap_or_else(|| "download".to_string())
+}
+
+// `name` with the extension that matches `kind`, so that a zip served as
+// setup.exe is not started as a program and an extensionless installer is
+pub fn with_kind(name: &str, kind: Option<FileKind>) -> String {
+    let Some(kind) = kind else {
+        return name.to_string();
+    };
+
+    match split_extension(name) {
+        (_, Some(extension)) if kind.accepts(extension) => name.to_string(),
+        (stem, _) => format!("{}.{}", stem, kind.extension()),
+    }
+}
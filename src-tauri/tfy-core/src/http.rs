@@ -0,0 +1,155 @@
+use crate::error::AppError;
+use futures_util::stream::{Stream, StreamExt};
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub type BodyStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>, AppError>> + Send>>;
+pub type ResponseFuture<'a> = Pin<Box<dyn Future<Output = Result<HttpResponse, AppError>> + Send + 'a>>;
+
+// Every download goes through one of these. The app shares a single client
+// through managed state, tests swap in their own.
+pub trait HttpClient: Send + Sync {
+    fn get<'a>(&'a self, url: &'a str, timeout: Option<Duration>) -> ResponseFuture<'a>;
+    // The body from byte `offset` on, through a Range header. Servers that
+    // don't do ranges answer 200 with the whole body, see resumes_at(), and so
+    // do those where the body no longer matches the `if_range` validator.
+    fn get_from<'a>(&'a self, url: &'a str, offset: u64, if_range: Option<&'a str>) -> ResponseFuture<'a>;
+}
+
+pub type SharedClient = Arc<dyn HttpClient>;
+
+pub struct HttpResponse {
+    pub status: u16,
+    // The URL the body came from, after following redirects
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: BodyStream,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        self.header("content-length")?.trim().parse().ok()
+    }
+
+    // True for a 206 whose Content-Range ("bytes 1024-4095/4096") starts at `offset`
+    pub fn resumes_at(&self, offset: u64) -> bool {
+        let start = self.header("content-range")
+            .and_then(|range| range.trim().strip_prefix("bytes "))
+            .and_then(|range| range.split('-').next())
+            .and_then(|start| start.trim().parse::<u64>().ok());
+        self.status == 206 && start == Some(offset)
+    }
+
+    // Fails on non-2xx statuses, `what` names the download in the error
+    pub fn error_for_status(self, what: &str) -> Result<HttpResponse, AppError> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(AppError::http_status(self.status, format!("Failed to fetch {}", what)))
+        }
+    }
+
+    // Reads the whole body, a connection that closes early is an error rather than a short file
+    pub async fn bytes(self) -> Result<Vec<u8>, AppError> {
+        let capacity = self.content_length().unwrap_or(0).min(64 * 1024 * 1024) as usize;
+        let mut bytes = Vec::with_capacity(capacity);
+        self.write_to(&mut bytes).await?;
+        Ok(bytes)
+    }
+
+    // Streams the body into `writer` chunk by chunk and returns the byte count,
+    // with the same truncation check as bytes()
+    pub async fn write_to<W: Write>(mut self, writer: &mut W) -> Result<u64, AppError> {
+        let expected = self.content_length();
+        let mut written = 0u64;
+        while let Some(chunk) = self.body.next().await {
+            let chunk = chunk?;
+            writer.write_all(&chunk)
+                .map_err(|e| AppError::io(format!("Failed to write response from {}", self.url), e))?;
+            written += chunk.len() as u64;
+        }
+
+        if let Some(expected) = expected {
+            if written != expected {
+                return Err(AppError::network(
+                    format!("Response from {} was truncated", self.url),
+                    format!("expected {} bytes, got {}", expected, written),
+                ));
+            }
+        }
+        Ok(written)
+    }
+
+    pub async fn text(self) -> Result<String, AppError> {
+        let url = self.url.clone();
+        String::from_utf8(self.bytes().await?)
+            .map_err(|e| AppError::validation(format!("Response from {} is not valid UTF-8: {}", url, e)))
+    }
+}
+
+#[derive(Default)]
+pub struct ReqwestClient {
+    client: reqwest::Client,
+}
+
+impl HttpClient for ReqwestClient {
+    fn get<'a>(&'a self, url: &'a str, timeout: Option<Duration>) -> ResponseFuture<'a> {
+        let mut request = self.client.get(url);
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        send(request, url)
+    }
+
+    fn get_from<'a>(&'a self, url: &'a str, offset: u64, if_range: Option<&'a str>) -> ResponseFuture<'a> {
+        let mut request = self.client.get(url).header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        if let Some(validator) = if_range {
+            request = request.header(reqwest::header::IF_RANGE, validator);
+        }
+        send(request, url)
+    }
+}
+
+fn send(request: reqwest::RequestBuilder, url: &str) -> ResponseFuture<'_> {
+    Box::pin(async move {
+        let response = request.send()
+            .await
+            .map_err(|e| AppError::network(format!("Failed to connect to {}", url), e))?;
+
+        let headers = response.headers().iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let status = response.status().as_u16();
+        let final_url = response.url().to_string();
+        let body_url = final_url.clone();
+        let body = response.bytes_stream().map(move |chunk| {
+            chunk
+                .map(|chunk| chunk.to_vec())
+                .map_err(|e| AppError::network(format!("Failed to read response from {}", body_url), e))
+        });
+
+        Ok(HttpResponse {
+            status,
+            url: final_url,
+            headers,
+            body: Box::pin(body),
+        })
+    })
+}
+
+pub fn default_client() -> SharedClient {
+    Arc::new(ReqwestClient::default())
+}
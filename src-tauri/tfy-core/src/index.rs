@@ -0,0 +1,120 @@
+//! Per-file hash index written into every install directory as `tfy-index.json`.
+//!
+//! The format is stable, readers must reject a `format` they do not know:
+//!
+//! ```json
+//! {
+//!   "format": 1,
+//!   "version": "version-0123456789abcdef",
+//!   "binary_type": "WindowsPlayer",
+//!   "files": {
+//!     "content/fonts/arial.ttf": {
+//!       "size": 367112,
+//!       "sha256": "<64 lowercase hex digits>",
+//!       "package": "content-fonts.zip"
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! Keys of `files` are paths relative to the install directory with `/` separators,
+//! sorted. Only files extracted from packages are listed, so AppSettings.xml,
+//! ClientSettings, rbxPkgManifest.txt and the index itself are not.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+pub const INDEX_NAME: &str = "tfy-index.json";
+pub const INDEX_FORMAT: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IndexedFile {
+    pub size: u64,
+    pub sha256: String,
+    pub package: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FileIndex {
+    pub format: u32,
+    pub version: String,
+    pub binary_type: String,
+    pub files: BTreeMap<String, IndexedFile>,
+}
+
+pub fn hash_file(path: &Path) -> Option<(u64, String)> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Some((size, format!("{:x}", hasher.finalize())))
+}
+
+impl FileIndex {
+    pub fn new(version: &str, binary_type: &str) -> FileIndex {
+        FileIndex {
+            format: INDEX_FORMAT,
+            version: version.to_string(),
+            binary_type: binary_type.to_string(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    pub fn load(install_dir: &Path) -> Result<Option<FileIndex>, AppError> {
+        let path = install_dir.join(INDEX_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let text = fs::read_to_string(&path)
+            .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?;
+        let index: FileIndex = serde_json::from_str(&text)
+            .map_err(|e| AppError::validation(format!("Failed to parse {}: {}", path.display(), e)))?;
+
+        if index.format != INDEX_FORMAT {
+            return Err(AppError::validation(format!("Unsupported index format: {}", index.format)));
+        }
+        Ok(Some(index))
+    }
+
+    pub fn save(&self, install_dir: &Path) -> Result<(), AppError> {
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::io("Failed to serialize file index", e))?;
+        fs::write(install_dir.join(INDEX_NAME), text)
+            .map_err(|e| AppError::io(format!("Failed to write {}", INDEX_NAME), e))
+    }
+
+    pub fn replace_package(&mut self, package: &str, files: BTreeMap<String, IndexedFile>) {
+        self.files.retain(|_, file| file.package != package);
+        self.files.extend(files);
+    }
+
+    // Package name to the files of it that are missing or no longer match their hash
+    pub fn damaged_files(&self, install_dir: &Path) -> BTreeMap<String, Vec<String>> {
+        let mut damaged: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        for (relative, expected) in &self.files {
+            let intact = hash_file(&install_dir.join(relative))
+                .is_some_and(|(size, sha256)| size == expected.size && sha256 == expected.sha256);
+
+            if !intact {
+                damaged.entry(expected.package.clone()).or_default().push(relative.clone());
+            }
+        }
+
+        damaged
+    }
+}
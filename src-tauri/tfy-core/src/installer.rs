@@ -0,0 +1,341 @@
+use crate::config::{self, AppSettingsOutcome};
+use crate::dedupe::{self, DedupeReport};
+use crate::disk;
+use crate::error::AppError;
+use crate::export;
+use crate::http::HttpClient;
+use crate::index::FileIndex;
+use crate::packages::{self, PackageSource};
+use crate::progress::ProgressSink;
+use crate::registry::{self, InstallRecord};
+use crate::repair::{self, RepairReport};
+use crate::staging;
+use crate::versions::{self, cdn_base_url, normalize_version};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn downloads_dir() -> Result<PathBuf, AppError> {
+    dirs::download_dir().ok_or_else(|| AppError::not_found("Could not find Downloads directory"))
+}
+
+// What download_player installs, and where from and to
+pub struct PlayerRequest {
+    pub version_hash: String,
+    pub channel: String,
+    pub binary_type: String,
+    // Pre-downloaded packages to install from instead of the CDN
+    pub package_dir: Option<PathBuf>,
+    // Replaces the channel's CDN, for mirrors
+    pub cdn_url: Option<String>,
+    // The version folder is created in here, Downloads by default
+    pub install_root: Option<PathBuf>,
+}
+
+impl PlayerRequest {
+    pub fn new(version_hash: impl Into<String>, channel: impl Into<String>, binary_type: impl Into<String>) -> PlayerRequest {
+        PlayerRequest {
+            version_hash: version_hash.into(),
+            channel: channel.into(),
+            binary_type: binary_type.into(),
+            package_dir: None,
+            cdn_url: None,
+            install_root: None,
+        }
+    }
+}
+
+pub async fn download_player(
+    client: &dyn HttpClient,
+    progress: &dyn ProgressSink,
+    request: &PlayerRequest,
+) -> Result<InstallRecord, AppError> {
+    let channel = request.channel.as_str();
+    let binary_type = request.binary_type.as_str();
+
+    progress.progress("▶ Starting download…");
+
+    let version_norm = normalize_version(&request.version_hash);
+
+    // Binary type configuration
+    let binary_config = versions::binary_config(binary_type)?;
+
+    // Packages come from a pre-downloaded folder when one is given, otherwise from the CDN
+    let source = match &request.package_dir {
+        Some(dir) => {
+            if !dir.is_dir() {
+                return Err(AppError::not_found(format!("Package directory not found: {}", dir.display())));
+            }
+            PackageSource::LocalDir(dir.clone())
+        }
+        None => PackageSource::Cdn {
+            client,
+            base_url: request.cdn_url.clone().unwrap_or_else(|| cdn_base_url(channel)),
+            blob_dir: binary_config.blob_dir,
+        },
+    };
+
+    // Fetch manifest
+    progress.progress(&format!("⎙ Fetching manifest: {}", source.describe_manifest(&version_norm)));
+
+    let manifest_text = source.fetch_manifest(&version_norm).await?;
+    let packages = packages::parse_manifest(&manifest_text)?;
+
+    if packages.is_empty() {
+        return Err(AppError::validation("No zip files found in manifest"));
+    }
+
+    // Determine extract roots based on binary type
+    let extract_roots = versions::extract_roots(binary_type);
+
+    // Create output directory in Downloads folder
+    let downloads_dir = match &request.install_root {
+        Some(install_root) => install_root.clone(),
+        None => downloads_dir()?,
+    };
+
+    // Make sure everything fits before the first file is written
+    let unpacked_size: u64 = packages.iter().map(|package| package.unpacked_size).sum();
+    disk::ensure_free_space(&downloads_dir, unpacked_size)?;
+    progress.progress(&format!("⎙ {} needed on disk", disk::format_size(unpacked_size)));
+
+    let output_dir = downloads_dir.join(&version_norm);
+    let staging_dir = staging::staging_dir(&output_dir);
+
+    let previous_dir = staging::previous_dir(&output_dir);
+    // Held until the end, so another process's sweep doesn't take the staging directory away
+    let _lock = staging::lock(&output_dir)?;
+
+    // Flag the staging directory and where commit() moves the old install
+    // first, so a crash leaves something to clean up on the next start
+    let mut registry = registry::load()?;
+    registry.mark_staging(&staging_dir)?;
+    registry.mark_staging(&previous_dir)?;
+
+    staging::prepare(&staging_dir, &output_dir)?;
+    progress.progress(&format!("⎙ Staging into: {}", staging_dir.display()));
+
+    let result: Result<(), AppError> = async {
+        // Create AppSettings.xml from the configured template
+        let app_config = config::load()?;
+        match app_config.app_settings.write(binary_type, &staging_dir)? {
+            AppSettingsOutcome::Written => progress.progress("⎙ Wrote AppSettings.xml"),
+            AppSettingsOutcome::Unchanged => {}
+            AppSettingsOutcome::KeptExisting => progress.progress("⎙ Keeping existing AppSettings.xml"),
+            AppSettingsOutcome::Replaced { backup } => {
+                progress.progress(&format!("⎙ Replaced invalid AppSettings.xml, backup saved to {}", backup.display()))
+            }
+            AppSettingsOutcome::Skipped => progress.progress("⎙ Skipping AppSettings.xml"),
+        }
+
+        // Download, verify and extract each package, indexing every file on the way
+        let mut file_index = FileIndex::new(&version_norm, binary_type);
+        for package in &packages {
+            progress.progress(&format!("↓ Downloading {}", package.name));
+
+            let zip_bytes = source.fetch_package(&version_norm, package).await?;
+
+            progress.progress(&format!("⎙ Extracting {}…", package.name));
+
+            // Fix the lifetime issue by using a default empty string
+            let default_root = String::new();
+            let extract_root = extract_roots.get(&package.name).unwrap_or(&default_root);
+
+            let files = packages::extract_package(&package.name, zip_bytes, &staging_dir, extract_root)?;
+            file_index.replace_package(&package.name, files);
+
+            progress.progress(&format!("→ {} done", package.name));
+        }
+        file_index.save(&staging_dir)?;
+
+        // Keep the manifest next to the files so the install can be exported later
+        fs::write(staging_dir.join("rbxPkgManifest.txt"), &manifest_text)
+            .map_err(|e| AppError::io("Failed to write rbxPkgManifest.txt", e))?;
+
+        staging::commit(&staging_dir, &output_dir)
+    }.await;
+
+    if let Err(e) = result {
+        progress.progress("✖ Rolling back partial install");
+        staging::discard(&staging_dir);
+        // Best effort, the install error is what gets reported. A directory
+        // left flagged is only swept once more on the next start.
+        if let Ok(mut registry) = registry::load() {
+            let _ = registry.unmark_staging(&staging_dir);
+            // Still there when the old install couldn't be moved back
+            if !previous_dir.exists() {
+                let _ = registry.unmark_staging(&previous_dir);
+            }
+        }
+        return Err(e);
+    }
+
+    progress.progress(&format!("⎙ Installed to: {}", output_dir.display()));
+
+    let record = InstallRecord {
+        version: version_norm,
+        channel: channel.to_string(),
+        binary_type: binary_type.to_string(),
+        path: output_dir,
+        installed_at: registry::now(),
+    };
+
+    let mut registry = registry::load()?;
+    // An old install commit() couldn't remove stays flagged for the next start
+    let previous_left = previous_dir.exists();
+    registry.staging.retain(|dir| dir != &staging_dir && (previous_left || dir != &previous_dir));
+    registry.upsert(record.clone());
+    registry.save()?;
+
+    progress.progress("✅ All files extracted successfully!");
+    Ok(record)
+}
+
+pub fn find_install(version_hash: &str, binary_type: Option<&str>) -> Result<InstallRecord, AppError> {
+    let registry = registry::load()?;
+    let record = registry.find(&normalize_version(version_hash), binary_type)?.clone();
+
+    if !record.path.is_dir() {
+        return Err(AppError::not_found(format!("Install folder is missing: {}", record.path.display())));
+    }
+    Ok(record)
+}
+
+pub fn verify_install(record: &InstallRecord) -> Result<BTreeMap<String, Vec<String>>, AppError> {
+    let file_index = FileIndex::load(&record.path)?
+        .ok_or_else(|| AppError::not_found(format!("{} has no file index, reinstall it to enable verification", record.version)))?;
+    Ok(file_index.damaged_files(&record.path))
+}
+
+pub async fn repair_install(
+    client: &dyn HttpClient,
+    progress: &dyn ProgressSink,
+    record: &InstallRecord,
+) -> Result<RepairReport, AppError> {
+    let binary_config = versions::binary_config(&record.binary_type)?;
+
+    let manifest_path = record.path.join("rbxPkgManifest.txt");
+    let manifest_text = fs::read_to_string(&manifest_path)
+        .map_err(|e| AppError::io(format!("Failed to read {}", manifest_path.display()), e))?;
+    let packages = packages::parse_manifest(&manifest_text)?;
+
+    let extract_roots = versions::extract_roots(&record.binary_type);
+
+    // Packages come from the package cache when possible, the rest from the CDN
+    let source = PackageSource::Cdn {
+        client,
+        base_url: cdn_base_url(&record.channel),
+        blob_dir: binary_config.blob_dir,
+    };
+
+    let mut report = RepairReport::default();
+    let mut file_index = FileIndex::load(&record.path)?;
+
+    // With an index only the packages whose files changed are fetched at all
+    let indexed_damage = file_index.as_ref().map(|file_index| {
+        progress.progress("⎙ Hashing files against tfy-index.json");
+        file_index.damaged_files(&record.path)
+    });
+
+    for package in &packages {
+        let default_root = String::new();
+        let extract_root = extract_roots.get(&package.name).unwrap_or(&default_root);
+
+        let (zip_bytes, damaged) = match &indexed_damage {
+            Some(indexed_damage) => {
+                report.packages_checked += 1;
+                let Some(damaged) = indexed_damage.get(&package.name) else {
+                    continue;
+                };
+                progress.progress(&format!("↓ Downloading {}", package.name));
+                (source.fetch_package(&record.version, package).await?, damaged.clone())
+            }
+            // Without one the zip directories say what each package should contain
+            None => {
+                progress.progress(&format!("⎙ Checking {}", package.name));
+                let files = repair::list_package(&source, &record.version, package).await?;
+                let damaged = repair::find_damaged(&files, &record.path, extract_root);
+                report.packages_checked += 1;
+                if damaged.is_empty() {
+                    continue;
+                }
+                progress.progress(&format!("↓ Downloading {}", package.name));
+                (source.fetch_package(&record.version, package).await?, damaged)
+            }
+        };
+
+        progress.progress(&format!("⎙ Repairing {} ({} files)", package.name, damaged.len()));
+        let files = packages::extract_package(&package.name, zip_bytes, &record.path, extract_root)?;
+        if let Some(file_index) = file_index.as_mut() {
+            file_index.replace_package(&package.name, files);
+        }
+        report.packages_repaired.push(package.name.clone());
+        report.damaged_files.extend(damaged);
+    }
+
+    if let Some(file_index) = &file_index {
+        file_index.save(&record.path)?;
+    }
+
+    progress.progress(&format!(
+        "✅ Checked {} packages, repaired {}",
+        report.packages_checked,
+        report.packages_repaired.len()
+    ));
+    Ok(report)
+}
+
+pub async fn dedupe_installs(dry_run: bool) -> Result<DedupeReport, AppError> {
+    let registry = registry::load()?;
+    let installs: Vec<InstallRecord> = registry.installs.into_iter()
+        .filter(|install| install.path.is_dir())
+        .collect();
+
+    tokio::task::spawn_blocking(move || dedupe::dedupe(&installs, dry_run))
+        .await
+        .map_err(|e| if e.is_cancelled() {
+            AppError::cancelled("Deduplication was cancelled")
+        } else {
+            AppError::io("Deduplication failed", e)
+        })?
+}
+
+// Returns the archive path and the number of files packed
+pub fn export_version(record: &InstallRecord, destination: Option<PathBuf>) -> Result<(PathBuf, usize), AppError> {
+    let archive_path = match destination {
+        Some(destination) => destination,
+        None => downloads_dir()?
+            .join("tfy-exports")
+            .join(format!("{}-{}.zip", record.version, record.binary_type)),
+    };
+
+    let count = export::export_install(record, &archive_path)?;
+    Ok((archive_path, count))
+}
+
+// Returns the new install and the number of files unpacked
+pub fn import_version(archive_path: &Path) -> Result<(InstallRecord, usize), AppError> {
+    let metadata = export::read_metadata(archive_path)?;
+    let version_norm = normalize_version(&metadata.version);
+
+    let output_dir = downloads_dir()?.join(&version_norm);
+    if output_dir.exists() {
+        return Err(AppError::validation(format!("{} already exists, remove it before importing", output_dir.display())));
+    }
+
+    let count = export::import_files(archive_path, &output_dir)?;
+
+    let record = InstallRecord {
+        version: version_norm,
+        channel: metadata.channel,
+        binary_type: metadata.binary_type,
+        path: output_dir,
+        installed_at: registry::now(),
+    };
+
+    let mut registry = registry::load()?;
+    registry.upsert(record.clone());
+    registry.save()?;
+
+    Ok((record, count))
+}
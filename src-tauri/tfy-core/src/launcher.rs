@@ -0,0 +1,102 @@
+use crate::error::AppError;
+use crate::registry::{self, InstallRecord};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::{Arc, Mutex};
+
+#[derive(Serialize, Clone)]
+pub struct LaunchInfo {
+    pub pid: u32,
+    pub version: String,
+    pub binary_type: String,
+    pub started_at: u64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct LaunchExit {
+    pub pid: u32,
+    pub version: String,
+    pub binary_type: String,
+    pub code: Option<i32>,
+    pub success: bool,
+}
+
+// Processes started from the app, removed again once they exit
+#[derive(Default)]
+pub struct Launches {
+    pub running: Arc<Mutex<HashMap<u32, LaunchInfo>>>,
+}
+
+pub fn find_executable(record: &InstallRecord) -> Result<PathBuf, AppError> {
+    let relative = match record.binary_type.as_str() {
+        "WindowsPlayer" => "RobloxPlayerBeta.exe",
+        "WindowsStudio64" => "RobloxStudioBeta.exe",
+        "MacPlayer" => "RobloxPlayer.app/Contents/MacOS/RobloxPlayer",
+        "MacStudio" => "RobloxStudio.app/Contents/MacOS/RobloxStudio",
+        other => return Err(AppError::validation(format!("Unknown binary type: {}", other))),
+    };
+
+    let executable = record.path.join(relative);
+    if !executable.is_file() {
+        return Err(AppError::not_found(format!("Executable not found: {}", executable.display())));
+    }
+    Ok(executable)
+}
+
+// On Linux hosts the executable can be run through a wrapper such as wine
+pub fn spawn(record: &InstallRecord, args: &[String], wrapper: Option<&[String]>) -> Result<Child, AppError> {
+    let executable = find_executable(record)?;
+
+    let mut command = match wrapper.filter(|wrapper| cfg!(target_os = "linux") && !wrapper.is_empty()) {
+        Some(wrapper) => {
+            let mut command = Command::new(&wrapper[0]);
+            command.args(&wrapper[1..]).arg(&executable);
+            command
+        }
+        None => Command::new(&executable),
+    };
+
+    command.args(args)
+        .current_dir(&record.path)
+        .spawn()
+        .map_err(|e| AppError::io(format!("Failed to launch {}", executable.display()), e))
+}
+
+impl Launches {
+    // Waits for the child on its own thread and reports the exit through `on_exit`
+    pub fn track<F>(&self, record: &InstallRecord, mut child: Child, on_exit: F) -> LaunchInfo
+    where
+        F: FnOnce(LaunchExit) + Send + 'static,
+    {
+        let info = LaunchInfo {
+            pid: child.id(),
+            version: record.version.clone(),
+            binary_type: record.binary_type.clone(),
+            started_at: registry::now(),
+        };
+        self.running.lock().unwrap().insert(info.pid, info.clone());
+
+        let running = Arc::clone(&self.running);
+        let exiting = info.clone();
+        std::thread::spawn(move || {
+            let status = child.wait().ok();
+            running.lock().unwrap().remove(&exiting.pid);
+
+            on_exit(LaunchExit {
+                pid: exiting.pid,
+                version: exiting.version,
+                binary_type: exiting.binary_type,
+                code: status.and_then(|status| status.code()),
+                success: status.is_some_and(|status| status.success()),
+            });
+        });
+
+        info
+    }
+
+    pub fn list(&self) -> Vec<LaunchInfo> {
+        self.running.lock().unwrap().values().cloned().collect()
+    }
+}
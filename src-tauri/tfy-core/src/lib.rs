@@ -0,0 +1,35 @@
+// Everything behind TFY Tool that doesn't need a window. The Tauri app and the
+// command line tools are thin shells over these modules.
+pub mod apps;
+pub mod bisect;
+pub mod catalog;
+pub mod config;
+pub mod dedupe;
+pub mod deploy_history;
+pub mod detect;
+pub mod disk;
+pub mod downloads;
+pub mod error;
+pub mod export;
+pub mod fflags;
+pub mod filename;
+pub mod http;
+pub mod index;
+pub mod installer;
+pub mod launcher;
+pub mod packages;
+pub mod pe;
+pub mod process;
+pub mod profiles;
+pub mod progress;
+pub mod registry;
+pub mod repair;
+pub mod setup;
+pub mod staging;
+pub mod sysinfo;
+pub mod tools;
+pub mod versions;
+
+pub use error::AppError;
+pub use http::{HttpClient, SharedClient};
+pub use progress::{NoProgress, ProgressSink};
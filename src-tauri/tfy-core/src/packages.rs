@@ -0,0 +1,308 @@
+use crate::error::AppError;
+use crate::http::HttpClient;
+use crate::index::IndexedFile;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use zip::read::ZipFile;
+use zip::ZipArchive;
+
+pub struct PackageEntry {
+    pub name: String,
+    pub checksum: String,
+    pub packed_size: u64,
+    pub unpacked_size: u64,
+}
+
+// rbxPkgManifest.txt is a "v0" header followed by groups of four lines:
+// package name, md5 checksum, packed size and unpacked size.
+pub fn parse_manifest(text: &str) -> Result<Vec<PackageEntry>, AppError> {
+    let mut lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty());
+
+    match lines.next() {
+        Some("v0") => {}
+        Some(other) => return Err(AppError::validation(format!("Unsupported manifest version: {}", other))),
+        None => return Err(AppError::validation("Manifest is empty")),
+    }
+
+    let lines: Vec<&str> = lines.collect();
+    let mut packages = Vec::new();
+
+    for group in lines.chunks(4) {
+        if group.len() != 4 {
+            return Err(AppError::validation(format!("Truncated manifest entry for {}", group[0])));
+        }
+
+        let parse_size = |value: &str| {
+            value.parse::<u64>()
+                .map_err(|_| AppError::validation(format!("Invalid size '{}' for {} in manifest", value, group[0])))
+        };
+
+        if group[1].len() != 32 || !group[1].chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(AppError::validation(format!("Invalid checksum '{}' for {} in manifest", group[1], group[0])));
+        }
+
+        packages.push(PackageEntry {
+            name: group[0].to_string(),
+            checksum: group[1].to_lowercase(),
+            packed_size: parse_size(group[2])?,
+            unpacked_size: parse_size(group[3])?,
+        });
+    }
+
+    // Only the zip packages are extracted, the manifest also lists loose files
+    packages.retain(|package| package.name.ends_with(".zip"));
+    Ok(packages)
+}
+
+// Where package blobs come from: the Roblox CDN, or a local folder mirrored from it
+pub enum PackageSource<'a> {
+    Cdn {
+        client: &'a dyn HttpClient,
+        base_url: String,
+        blob_dir: String,
+    },
+    LocalDir(PathBuf),
+}
+
+impl PackageSource<'_> {
+    pub fn describe_manifest(&self, version: &str) -> String {
+        match self {
+            PackageSource::Cdn { base_url, blob_dir, .. } => cdn_url(base_url, blob_dir, version, "rbxPkgManifest.txt"),
+            PackageSource::LocalDir(dir) => dir.display().to_string(),
+        }
+    }
+
+    pub async fn fetch_manifest(&self, version: &str) -> Result<String, AppError> {
+        match self {
+            PackageSource::Cdn { client, base_url, blob_dir } => {
+                let url = cdn_url(base_url, blob_dir, version, "rbxPkgManifest.txt");
+                let response = client.get(&url, Some(Duration::from_secs(30))).await?;
+
+                if response.status == 403 {
+                    return Err(AppError::http_status(403, "Version hash invalid or service unavailable"));
+                }
+
+                response.error_for_status("manifest")?.text().await
+            }
+            PackageSource::LocalDir(dir) => {
+                let path = find_local_file(dir, version, "rbxPkgManifest.txt")?;
+                fs::read_to_string(&path)
+                    .map_err(|e| AppError::io(format!("Failed to read manifest {}", path.display()), e))
+            }
+        }
+    }
+
+    pub async fn fetch_package(&self, version: &str, package: &PackageEntry) -> Result<Vec<u8>, AppError> {
+        let bytes = match self {
+            PackageSource::Cdn { client, base_url, blob_dir } => {
+                if let Some(bytes) = read_cached(package) {
+                    return Ok(bytes);
+                }
+
+                let url = cdn_url(base_url, blob_dir, version, &package.name);
+                client.get(&url, Some(Duration::from_secs(60)))
+                    .await?
+                    .error_for_status(&package.name)?
+                    .bytes()
+                    .await?
+            }
+            PackageSource::LocalDir(dir) => {
+                let path = find_local_file(dir, version, &package.name)?;
+                fs::read(&path)
+                    .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?
+            }
+        };
+
+        verify_package(package, &bytes)?;
+        if let PackageSource::Cdn { .. } = self {
+            store_cached(package, &bytes);
+        }
+        Ok(bytes)
+    }
+
+    // The end of a package from byte `offset` on, e.g. its zip directory. From
+    // the CDN that is a range request, a server that ignores it sends the whole
+    // package, which is verified and cached like fetch_package() does.
+    pub async fn fetch_tail(&self, version: &str, package: &PackageEntry, offset: u64) -> Result<Vec<u8>, AppError> {
+        let tail = |bytes: Vec<u8>| bytes.get(offset as usize..).unwrap_or_default().to_vec();
+
+        match self {
+            PackageSource::Cdn { client, base_url, blob_dir } => {
+                if let Some(bytes) = read_cached(package) {
+                    return Ok(tail(bytes));
+                }
+
+                let url = cdn_url(base_url, blob_dir, version, &package.name);
+                let response = client.get_from(&url, offset, None).await?;
+                if response.resumes_at(offset) {
+                    return response.bytes().await;
+                }
+
+                let bytes = response.error_for_status(&package.name)?.bytes().await?;
+                verify_package(package, &bytes)?;
+                store_cached(package, &bytes);
+                Ok(tail(bytes))
+            }
+            PackageSource::LocalDir(dir) => {
+                let path = find_local_file(dir, version, &package.name)?;
+                let mut bytes = Vec::new();
+                fs::File::open(&path)
+                    .and_then(|mut file| {
+                        file.seek(SeekFrom::Start(offset))?;
+                        file.read_to_end(&mut bytes)
+                    })
+                    .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?;
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+// Verified CDN blobs are kept by checksum so re-installs and bisecting skip the download
+fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("tfy-tool").join("packages"))
+}
+
+fn read_cached(package: &PackageEntry) -> Option<Vec<u8>> {
+    let path = cache_dir()?.join(&package.checksum);
+    let bytes = fs::read(path).ok()?;
+    verify_package(package, &bytes).ok()?;
+    Some(bytes)
+}
+
+// The cache is best effort, a failed write only costs a download next time
+fn store_cached(package: &PackageEntry, bytes: &[u8]) {
+    if let Some(dir) = cache_dir() {
+        if fs::create_dir_all(&dir).is_ok() {
+            let _ = fs::write(dir.join(&package.checksum), bytes);
+        }
+    }
+}
+
+fn cdn_url(base_url: &str, blob_dir: &str, version: &str, name: &str) -> String {
+    format!("{}{}{}-{}", base_url, blob_dir, version, name)
+}
+
+// Mirrored folders keep the CDN names ("version-xxx-RobloxApp.zip"), but plain names are accepted too
+fn find_local_file(dir: &Path, version: &str, name: &str) -> Result<PathBuf, AppError> {
+    let candidates = [dir.join(format!("{}-{}", version, name)), dir.join(name)];
+
+    candidates.iter()
+        .find(|path| path.is_file())
+        .cloned()
+        .ok_or_else(|| AppError::not_found(format!("{} not found in {}", name, dir.display())))
+}
+
+fn verify_package(package: &PackageEntry, bytes: &[u8]) -> Result<(), AppError> {
+    if bytes.len() as u64 != package.packed_size {
+        return Err(AppError::validation(format!(
+            "Size mismatch for {}: expected {} bytes, got {}",
+            package.name, package.packed_size, bytes.len()
+        )));
+    }
+
+    let checksum = format!("{:x}", Md5::digest(bytes));
+    if checksum != package.checksum {
+        return Err(AppError::validation(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            package.name, package.checksum, checksum
+        )));
+    }
+
+    Ok(())
+}
+
+// Writes through to the file while hashing, so the index costs no second read
+pub(crate) struct HashingWriter<W> {
+    pub inner: W,
+    pub hasher: Sha256,
+    pub size: u64,
+}
+
+impl<W> HashingWriter<W> {
+    pub fn new(inner: W) -> HashingWriter<W> {
+        HashingWriter { inner, hasher: Sha256::new(), size: 0 }
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// The entry's name with forward slashes, None when it would land outside the
+// folder it is extracted to. enclosed_name rejects absolute paths and ".."
+// components, Roblox packages use backslashes so it is checked again after.
+pub(crate) fn entry_path(file: &ZipFile) -> Option<String> {
+    let entry = file.name().replace('\\', "/");
+    let normal = Path::new(&entry).components().all(|component| matches!(component, Component::Normal(_)));
+    (file.enclosed_name().is_some() && normal).then_some(entry)
+}
+
+// Returns the index entries of every extracted file, keyed relative to `install_dir`
+pub fn extract_package(
+    name: &str,
+    bytes: Vec<u8>,
+    install_dir: &Path,
+    extract_root: &str,
+) -> Result<BTreeMap<String, IndexedFile>, AppError> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| AppError::archive(format!("Failed to open zip {}", name), e))?;
+
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)
+            .map_err(|e| AppError::archive(format!("Failed to read file from {}", name), e))?;
+
+        if file.is_dir() {
+            continue;
+        }
+
+        let entry = entry_path(&file)
+            .ok_or_else(|| AppError::validation(format!("{} contains an unsafe path: {}", name, file.name())))?;
+
+        let relative = format!("{}{}", extract_root, entry);
+        let file_path = install_dir.join(&relative);
+
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::io(format!("Failed to create directory {}", parent.display()), e))?;
+        }
+
+        // The old file may be hardlinked to the same file in another install by
+        // dedupe, writing through it would change both
+        match fs::remove_file(&file_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(AppError::io(format!("Failed to replace file {}", file_path.display()), e));
+            }
+            _ => {}
+        }
+        let output_file = fs::File::create(&file_path)
+            .map_err(|e| AppError::io(format!("Failed to create file {}", file_path.display()), e))?;
+        let mut writer = HashingWriter::new(output_file);
+
+        std::io::copy(&mut file, &mut writer)
+            .map_err(|e| AppError::archive(format!("Failed to extract file {}", file_path.display()), e))?;
+
+        files.insert(relative, IndexedFile {
+            size: writer.size,
+            sha256: format!("{:x}", writer.hasher.finalize()),
+            package: name.to_string(),
+        });
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,418 @@
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Reads what Explorer's Properties dialog shows for an .exe: PE header fields,
+// the version resource strings and who the Authenticode signature says it is
+// from. Nothing here checks the signature cryptographically, Windows still does
+// that when the file is run. A pinned sha256 is what proves the bytes are right.
+const RT_VERSION: u32 = 16;
+const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 2;
+const RESOURCE_DIRECTORY: usize = 2;
+const SECURITY_DIRECTORY: usize = 4;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PeInfo {
+    pub machine: String,
+    pub subsystem: String,
+    pub company_name: Option<String>,
+    pub product_name: Option<String>,
+    pub file_version: Option<String>,
+    // An Authenticode blob is present, not that it is valid
+    pub signed: bool,
+    // Subject of the signing certificate, in the order Windows shows it ("CN=..., O=..., C=...")
+    pub signer: Option<String>,
+    // The common name in `signer`. Taken from the certificate as is, nothing
+    // checks that it chains to a trusted root, so anyone can claim any name.
+    pub claimed_publisher: Option<String>,
+}
+
+impl PeInfo {
+    // One line for the UI
+    pub fn describe(&self) -> String {
+        let product = match (&self.product_name, &self.file_version) {
+            (Some(name), Some(version)) => format!("{} {}", name, version),
+            (Some(name), None) => name.clone(),
+            (None, Some(version)) => format!("version {}", version),
+            (None, None) => "no version info".to_string(),
+        };
+        let signature = match &self.claimed_publisher {
+            Some(publisher) => format!("claims to be signed by {} (not verified)", publisher),
+            None if self.signed => "has a signature, signer unreadable".to_string(),
+            None => "not signed".to_string(),
+        };
+        format!("{} ({} {}), {}", product, self.machine, self.subsystem, signature)
+    }
+}
+
+fn invalid(reason: &str) -> AppError {
+    AppError::validation(format!("Not a valid Windows executable: {}", reason))
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(offset..offset.checked_add(2)?)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset.checked_add(4)?)?.try_into().ok()?))
+}
+
+// Byte ranges of an executable, from memory or read from the file on demand
+trait Image {
+    fn read(&self, offset: usize, length: usize) -> Option<Vec<u8>>;
+
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        u16_at(&self.read(offset, 2)?, 0)
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        u32_at(&self.read(offset, 4)?, 0)
+    }
+}
+
+impl Image for [u8] {
+    fn read(&self, offset: usize, length: usize) -> Option<Vec<u8>> {
+        self.get(offset..offset.checked_add(length)?).map(<[u8]>::to_vec)
+    }
+}
+
+struct FileImage {
+    file: fs::File,
+    length: u64,
+}
+
+impl Image for FileImage {
+    // Ranges past the end are None like for a slice, so a corrupt size is never allocated
+    fn read(&self, offset: usize, length: usize) -> Option<Vec<u8>> {
+        let end = offset.checked_add(length)?;
+        if end as u64 > self.length {
+            return None;
+        }
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset as u64)).ok()?;
+        let mut buffer = vec![0; length];
+        file.read_exact(&mut buffer).ok()?;
+        Some(buffer)
+    }
+}
+
+fn machine_name(machine: u16) -> String {
+    match machine {
+        0x014c => "x86".to_string(),
+        0x8664 => "x64".to_string(),
+        0xaa64 => "arm64".to_string(),
+        0x01c4 => "arm".to_string(),
+        other => format!("unknown (0x{:04x})", other),
+    }
+}
+
+fn subsystem_name(subsystem: u16) -> String {
+    match subsystem {
+        1 => "native".to_string(),
+        2 => "gui".to_string(),
+        3 => "console".to_string(),
+        10 => "efi".to_string(),
+        other => format!("unknown ({})", other),
+    }
+}
+
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+}
+
+fn rva_to_offset(sections: &[Section], rva: u32) -> Option<usize> {
+    sections.iter().find_map(|section| {
+        let delta = rva.checked_sub(section.virtual_address)?;
+        if delta >= section.virtual_size.max(section.raw_size) {
+            return None;
+        }
+        Some(section.raw_offset as usize + delta as usize)
+    })
+}
+
+pub fn inspect(data: &[u8]) -> Result<PeInfo, AppError> {
+    parse(data)
+}
+
+// Reads the headers, the resource section and the certificate table with
+// seeks, so inspecting a large installer doesn't load all of it
+pub fn inspect_file(path: &Path) -> Result<PeInfo, AppError> {
+    let file = fs::File::open(path)
+        .map_err(|e| AppError::io(format!("Failed to open {}", path.display()), e))?;
+    let length = file.metadata()
+        .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?
+        .len();
+    parse(&FileImage { file, length })
+}
+
+fn parse<I: Image + ?Sized>(data: &I) -> Result<PeInfo, AppError> {
+    if data.read(0, 2).as_deref() != Some(b"MZ") {
+        return Err(invalid("missing MZ header"));
+    }
+    let pe = data.u32_at(0x3c).ok_or_else(|| invalid("truncated DOS header"))? as usize;
+    if data.read(pe, 4).as_deref() != Some(b"PE\0\0") {
+        return Err(invalid("missing PE signature"));
+    }
+
+    let coff = pe + 4;
+    let truncated = || invalid("truncated headers");
+    let machine = data.u16_at(coff).ok_or_else(truncated)?;
+    let section_count = data.u16_at(coff + 2).ok_or_else(truncated)? as usize;
+    let optional_size = data.u16_at(coff + 16).ok_or_else(truncated)? as usize;
+
+    let optional = coff + 20;
+    let directories = match data.u16_at(optional).ok_or_else(truncated)? {
+        0x10b => optional + 96,
+        0x20b => optional + 112,
+        magic => return Err(invalid(&format!("unknown optional header magic 0x{:x}", magic))),
+    };
+    let subsystem = data.u16_at(optional + 68).ok_or_else(truncated)?;
+    let directory_count = data.u32_at(directories - 4).ok_or_else(truncated)? as usize;
+    let directory = |index: usize| -> Option<(u32, u32)> {
+        if index >= directory_count {
+            return None;
+        }
+        let entry = directories + index * 8;
+        Some((data.u32_at(entry)?, data.u32_at(entry + 4)?)).filter(|(_, size)| *size > 0)
+    };
+
+    let section_table = optional + optional_size;
+    let sections = (0..section_count)
+        .map(|i| {
+            let header = section_table + i * 40;
+            Some(Section {
+                virtual_size: data.u32_at(header + 8)?,
+                virtual_address: data.u32_at(header + 12)?,
+                raw_size: data.u32_at(header + 16)?,
+                raw_offset: data.u32_at(header + 20)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| invalid("truncated section table"))?;
+
+    // Damaged resources or certificates just leave those fields empty, the
+    // headers above are enough to call it an executable
+    let strings = directory(RESOURCE_DIRECTORY)
+        .and_then(|(rva, _)| version_resource(data, &sections, rva))
+        .map(|resource| version_strings(&resource))
+        .unwrap_or_default();
+    let string = |key: &str| strings.iter().find(|(name, _)| name == key).map(|(_, value)| value.clone());
+
+    // The security directory holds a file offset, not an RVA
+    let certificate = directory(SECURITY_DIRECTORY).and_then(|(offset, size)| {
+        let table = data.read(offset as usize, size as usize)?;
+        let length = u32_at(&table, 0)? as usize;
+        if u16_at(&table, 6)? != WIN_CERT_TYPE_PKCS_SIGNED_DATA || length <= 8 {
+            return None;
+        }
+        table.get(8..length).map(<[u8]>::to_vec)
+    });
+    let subject = certificate.as_deref().and_then(signer_subject);
+
+    Ok(PeInfo {
+        machine: machine_name(machine),
+        subsystem: subsystem_name(subsystem),
+        company_name: string("CompanyName"),
+        product_name: string("ProductName"),
+        file_version: string("FileVersion"),
+        signed: certificate.is_some(),
+        signer: subject.as_ref().map(|subject| format_name(subject)),
+        claimed_publisher: subject.and_then(|subject| {
+            subject.into_iter().find(|(key, _)| key == "CN").map(|(_, value)| value)
+        }),
+    })
+}
+
+// Follows type RT_VERSION -> first name -> first language down to the data
+fn version_resource<I: Image + ?Sized>(data: &I, sections: &[Section], rva: u32) -> Option<Vec<u8>> {
+    let root = rva_to_offset(sections, rva)?;
+    let entries = |directory: usize| -> Option<Vec<(u32, u32)>> {
+        let count = data.u16_at(root + directory + 12)? as usize + data.u16_at(root + directory + 14)? as usize;
+        (0..count)
+            .map(|i| {
+                let entry = root + directory + 16 + i * 8;
+                Some((data.u32_at(entry)?, data.u32_at(entry + 4)?))
+            })
+            .collect()
+    };
+    let subdirectory = |offset: u32| (offset & 0x8000_0000 != 0).then_some((offset & 0x7fff_ffff) as usize);
+
+    let (_, types) = entries(0)?.into_iter().find(|(id, _)| *id == RT_VERSION)?;
+    let (_, names) = *entries(subdirectory(types)?)?.first()?;
+    let (_, languages) = *entries(subdirectory(names)?)?.first()?;
+    if subdirectory(languages).is_some() {
+        return None;
+    }
+
+    let leaf = root + languages as usize;
+    let offset = rva_to_offset(sections, data.u32_at(leaf)?)?;
+    let size = data.u32_at(leaf + 4)? as usize;
+    data.read(offset, size)
+}
+
+struct VersionBlock<'a> {
+    key: String,
+    // Everything after the key, for String blocks this is the UTF-16 value
+    value: &'a [u8],
+    children: &'a [u8],
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn utf16_until_nul(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+// wLength, wValueLength, wType, key, padding, value, padding, children
+fn version_block(data: &[u8]) -> Option<(VersionBlock<'_>, usize)> {
+    let length = u16_at(data, 0)? as usize;
+    let value_length = u16_at(data, 2)? as usize;
+    let is_text = u16_at(data, 4)? == 1;
+    let block = data.get(..length)?;
+
+    let key = utf16_until_nul(block.get(6..)?);
+    let value_start = align4(6 + (key.encode_utf16().count() + 1) * 2).min(length);
+    let value_bytes = if is_text { value_length * 2 } else { value_length };
+    let children_start = align4(value_start + value_bytes).min(length);
+
+    Some((
+        VersionBlock { key, value: &block[value_start..], children: &block[children_start..] },
+        align4(length).max(4),
+    ))
+}
+
+fn version_children(mut data: &[u8]) -> Vec<VersionBlock<'_>> {
+    let mut blocks = Vec::new();
+    while let Some((block, length)) = version_block(data) {
+        blocks.push(block);
+        data = data.get(length..).unwrap_or_default();
+    }
+    blocks
+}
+
+// (key, value) pairs of every string table, in file order
+fn version_strings(resource: &[u8]) -> Vec<(String, String)> {
+    let Some((root, _)) = version_block(resource).filter(|(root, _)| root.key == "VS_VERSION_INFO") else {
+        return Vec::new();
+    };
+
+    version_children(root.children)
+        .into_iter()
+        .filter(|block| block.key == "StringFileInfo")
+        .flat_map(|info| version_children(info.children))
+        .flat_map(|table| version_children(table.children))
+        .map(|string| (string.key, utf16_until_nul(string.value).trim().to_string()))
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+}
+
+// One DER element: (tag, contents, the whole element, what follows it)
+type Der<'a> = (u8, &'a [u8], &'a [u8], &'a [u8]);
+
+fn der(data: &[u8]) -> Option<Der<'_>> {
+    let tag = *data.first()?;
+    let first = *data.get(1)? as usize;
+    let (length, header) = if first < 0x80 {
+        (first, 2)
+    } else {
+        let count = first & 0x7f;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let length = data.get(2..2 + count)?.iter().fold(0usize, |length, byte| length << 8 | *byte as usize);
+        (length, 2 + count)
+    };
+    let end = header.checked_add(length)?;
+    Some((tag, data.get(header..end)?, &data[..end], &data[end..]))
+}
+
+fn der_elements(mut data: &[u8]) -> Vec<Der<'_>> {
+    let mut elements = Vec::new();
+    while let Some(element) = der(data) {
+        data = element.3;
+        elements.push(element);
+    }
+    elements
+}
+
+// PKCS#7 ContentInfo -> SignedData, then the certificate named by the first
+// SignerInfo's issuer and serial number
+fn signer_subject(pkcs7: &[u8]) -> Option<Vec<(String, String)>> {
+    let (_, content_info, _, _) = der(pkcs7)?;
+    let (_, _oid, _, rest) = der(content_info)?;
+    let (0xa0, explicit, _, _) = der(rest)? else { return None };
+    let (0x30, signed_data, _, _) = der(explicit)? else { return None };
+
+    let elements = der_elements(signed_data);
+    // version, digestAlgorithms, contentInfo, then [0] certificates, [1] crls and signerInfos
+    let tail = elements.get(3..)?;
+    let certificates = tail.iter().find(|element| element.0 == 0xa0)?.1;
+    let signer_infos = tail.iter().rev().find(|element| element.0 == 0x31)?.1;
+
+    let (_, signer_info, _, _) = der(signer_infos)?;
+    let (_, _version, _, rest) = der(signer_info)?;
+    let (0x30, issuer_and_serial, _, _) = der(rest)? else { return None };
+    let (_, _, issuer, rest) = der(issuer_and_serial)?;
+    let (_, serial, _, _) = der(rest)?;
+
+    der_elements(certificates).into_iter().find_map(|(_, certificate, _, _)| {
+        let (_, tbs, _, _) = der(certificate)?;
+        let mut fields = der_elements(tbs);
+        if fields.first()?.0 == 0xa0 {
+            fields.remove(0);
+        }
+        // serialNumber, signature, issuer, validity, subject
+        if fields.first()?.1 != serial || fields.get(2)?.2 != issuer {
+            return None;
+        }
+        Some(name_attributes(fields.get(4)?.1))
+    })
+}
+
+fn name_attributes(name: &[u8]) -> Vec<(String, String)> {
+    der_elements(name)
+        .into_iter()
+        .flat_map(|(_, set, _, _)| der_elements(set))
+        .filter_map(|(_, attribute, _, _)| {
+            let (_, oid, _, rest) = der(attribute)?;
+            let (tag, value, _, _) = der(rest)?;
+            let key = match oid {
+                [0x55, 0x04, 0x03] => "CN",
+                [0x55, 0x04, 0x0a] => "O",
+                [0x55, 0x04, 0x0b] => "OU",
+                [0x55, 0x04, 0x07] => "L",
+                [0x55, 0x04, 0x08] => "S",
+                [0x55, 0x04, 0x06] => "C",
+                _ => return None,
+            };
+            let value = if tag == 0x1e {
+                // BMPString is UTF-16 big endian
+                let units: Vec<u16> = value.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+                String::from_utf16_lossy(&units)
+            } else {
+                String::from_utf8_lossy(value).into_owned()
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+// Certificates usually encode the country first, Windows lists the common name first
+fn format_name(attributes: &[(String, String)]) -> String {
+    attributes.iter()
+        .rev()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,259 @@
+use crate::catalog::Catalog;
+use crate::downloads::DownloadManager;
+use crate::error::AppError;
+use crate::filename;
+use crate::progress::ProgressSink;
+use crate::registry;
+use crate::setup::{self, Confirm, Confirmed, InstallOutcome};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Named lists of catalog apps, e.g. "Gaming rig" = Steam, Discord, VLC and
+// Brave, installed one after the other on a fresh machine. Each profile is
+// a small JSON file in the app data directory that can be copied between
+// machines with import() and export().
+const PROFILE_FORMAT: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InstallProfile {
+    #[serde(default = "default_format")]
+    pub format: u32,
+    pub name: String,
+    // Catalog app ids, installed in this order
+    pub apps: Vec<String>,
+}
+
+fn default_format() -> u32 {
+    PROFILE_FORMAT
+}
+
+impl InstallProfile {
+    pub fn new(name: &str, apps: Vec<String>) -> InstallProfile {
+        InstallProfile { format: PROFILE_FORMAT, name: name.trim().to_string(), apps }
+    }
+
+    // Every app has to be in `catalog`, once, and install without dialogs
+    pub fn validate(&self, catalog: &Catalog) -> Result<(), AppError> {
+        if self.format != PROFILE_FORMAT {
+            return Err(AppError::validation(format!("Unsupported install profile format: {}", self.format)));
+        }
+        if self.name.trim().is_empty() || filename::sanitize(&self.name).is_none() {
+            return Err(AppError::validation(format!("Invalid install profile name '{}'", self.name)));
+        }
+        if self.apps.is_empty() {
+            return Err(AppError::validation(format!("Install profile '{}' has no apps", self.name)));
+        }
+        for (i, id) in self.apps.iter().enumerate() {
+            if self.apps[..i].contains(id) {
+                return Err(AppError::validation(format!("Install profile '{}' lists {} twice", self.name, id)));
+            }
+            let app = catalog.find(id)?;
+            if !setup::is_unattended(app) {
+                return Err(AppError::validation(format!(
+                    "Install profile '{}' can't include {}, it has no unattended install",
+                    self.name, app.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn profiles_dir() -> Result<PathBuf, AppError> {
+    let data_dir = dirs::data_dir()
+        .ok_or_else(|| AppError::not_found("Could not find app data directory"))?;
+    Ok(data_dir.join("tfy-tool").join("profiles"))
+}
+
+// Names are matched case-insensitively, "Gaming rig" and "gaming rig" share a file
+fn profile_path(name: &str) -> Result<PathBuf, AppError> {
+    let stem = filename::sanitize(&name.trim().to_lowercase())
+        .ok_or_else(|| AppError::validation(format!("Invalid install profile name '{}'", name)))?;
+    Ok(profiles_dir()?.join(format!("{}.json", stem)))
+}
+
+fn read(path: &Path) -> Result<InstallProfile, AppError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| AppError::io(format!("Failed to read {}", path.display()), e))?;
+    serde_json::from_str(&text)
+        .map_err(|e| AppError::validation(format!("Failed to parse install profile {}: {}", path.display(), e)))
+}
+
+fn write(profile: &InstallProfile, path: &Path) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io(format!("Failed to create {}", parent.display()), e))?;
+    }
+    let text = serde_json::to_string_pretty(profile)
+        .map_err(|e| AppError::io("Failed to serialize install profile", e))?;
+    fs::write(path, text)
+        .map_err(|e| AppError::io(format!("Failed to write {}", path.display()), e))
+}
+
+// Sorted by name. Files that no longer parse are skipped rather than hiding the rest.
+pub fn list() -> Result<Vec<InstallProfile>, AppError> {
+    let dir = profiles_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&dir)
+        .map_err(|e| AppError::io(format!("Failed to read {}", dir.display()), e))?;
+    let mut profiles: Vec<InstallProfile> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| read(&path).ok())
+        .collect();
+    profiles.sort_by_key(|profile| profile.name.to_lowercase());
+    Ok(profiles)
+}
+
+pub fn load(name: &str) -> Result<InstallProfile, AppError> {
+    let path = profile_path(name)?;
+    if !path.exists() {
+        return Err(AppError::not_found(format!("Unknown install profile: {}", name)));
+    }
+    read(&path)
+}
+
+// Creates or replaces the profile with the same name
+pub fn save(profile: &InstallProfile, catalog: &Catalog) -> Result<(), AppError> {
+    profile.validate(catalog)?;
+    write(profile, &profile_path(&profile.name)?)
+}
+
+pub fn delete(name: &str) -> Result<(), AppError> {
+    let path = profile_path(name)?;
+    fs::remove_file(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::not_found(format!("Unknown install profile: {}", name)),
+        _ => AppError::io(format!("Failed to delete {}", path.display()), e),
+    })
+}
+
+// Reads a profile exported on another machine and saves it here
+pub fn import(path: &Path, catalog: &Catalog) -> Result<InstallProfile, AppError> {
+    let profile = read(path)?;
+    save(&profile, catalog)?;
+    Ok(profile)
+}
+
+pub fn export(name: &str, path: &Path) -> Result<InstallProfile, AppError> {
+    let profile = load(name)?;
+    write(&profile, path)?;
+    Ok(profile)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProfileStep {
+    pub app_id: String,
+    pub name: String,
+    pub outcome: InstallOutcome,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProfileReport {
+    pub profile: String,
+    pub steps: Vec<ProfileStep>,
+    pub started_at: u64,
+    pub finished_at: u64,
+    // describe(), for the UI
+    pub summary: String,
+}
+
+impl ProfileReport {
+    pub fn count(&self, outcome: InstallOutcome) -> usize {
+        self.steps.iter().filter(|step| step.outcome == outcome).count()
+    }
+
+    pub fn reboot_required(&self) -> bool {
+        self.count(InstallOutcome::RebootRequired) > 0
+    }
+
+    // "Gaming rig: 3 of 4 apps installed, 1 failed. Restart Windows to finish."
+    pub fn describe(&self) -> String {
+        let failed = self.count(InstallOutcome::Failed);
+        let mut summary = format!(
+            "{}: {} of {} apps installed",
+            self.profile,
+            self.steps.len() - failed,
+            self.steps.len()
+        );
+        if failed > 0 {
+            summary.push_str(&format!(", {} failed", failed));
+        }
+        summary.push('.');
+        if self.reboot_required() {
+            summary.push_str(" Restart Windows to finish.");
+        }
+        summary
+    }
+}
+
+// Downloads, verifies and installs every app of `profile` in order through
+// `downloads`, after asking once for the whole profile. One app failing
+// doesn't stop the rest, its error ends up in the report instead.
+pub async fn run_profile(
+    downloads: &DownloadManager,
+    catalog: &Catalog,
+    profile: &InstallProfile,
+    dir: &Path,
+    progress: &dyn ProgressSink,
+    confirm: &dyn Confirm,
+) -> Result<ProfileReport, AppError> {
+    let names: Vec<&str> = profile.apps.iter()
+        .map(|id| catalog.find(id).map(|app| app.name.as_str()).unwrap_or(id))
+        .collect();
+    let message = format!("Downloads and installs {}, one after the other.\n\nStart now?", names.join(", "));
+    if !setup::confirmed(confirm, &format!("Run {}?", profile.name), &message) {
+        return Err(AppError::cancelled(format!("{} was cancelled", profile.name)));
+    }
+
+    let started_at = registry::now();
+    let mut steps = Vec::new();
+
+    for (i, id) in profile.apps.iter().enumerate() {
+        let app = match catalog.find(id) {
+            Ok(app) => app,
+            Err(e) => {
+                steps.push(ProfileStep {
+                    app_id: id.clone(),
+                    name: id.clone(),
+                    outcome: InstallOutcome::Failed,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        progress.progress(&format!("Installing {} ({}/{})", app.name, i + 1, profile.apps.len()));
+        let step = match setup::install_app(downloads, app, dir, &Confirmed).await {
+            Ok(result) => ProfileStep {
+                app_id: app.id.clone(),
+                name: app.name.clone(),
+                outcome: result.outcome,
+                message: result.message,
+            },
+            Err(e) => ProfileStep {
+                app_id: app.id.clone(),
+                name: app.name.clone(),
+                outcome: InstallOutcome::Failed,
+                message: format!("{}: {}", app.name, e),
+            },
+        };
+        progress.progress(&step.message);
+        steps.push(step);
+    }
+
+    let mut report = ProfileReport {
+        profile: profile.name.clone(),
+        steps,
+        started_at,
+        finished_at: registry::now(),
+        summary: String::new(),
+    };
+    report.summary = report.describe();
+    Ok(report)
+}
@@ -0,0 +1,18 @@
+// Long running operations report human readable progress lines through this.
+// The Tauri shell forwards them as "roblox-progress" events, the CLI prints them.
+pub trait ProgressSink: Send + Sync {
+    fn progress(&self, message: &str);
+}
+
+impl<F: Fn(&str) + Send + Sync> ProgressSink for F {
+    fn progress(&self, message: &str) {
+        self(message)
+    }
+}
+
+// For callers that don't care about progress
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn progress(&self, _message: &str) {}
+}
@@ -0,0 +1,114 @@
+use crate::error::AppError;
+use crate::staging;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InstallRecord {
+    pub version: String,
+    pub channel: String,
+    pub binary_type: String,
+    pub path: PathBuf,
+    pub installed_at: u64,
+}
+
+// Every version folder the app has installed, kept in the app data directory
+#[derive(Serialize, Deserialize, Default)]
+pub struct Registry {
+    pub installs: Vec<InstallRecord>,
+    #[serde(default)]
+    pub staging: Vec<PathBuf>,
+}
+
+fn registry_path() -> Result<PathBuf, AppError> {
+    let data_dir = dirs::data_dir()
+        .ok_or_else(|| AppError::not_found("Could not find app data directory"))?;
+    Ok(data_dir.join("tfy-tool").join("installs.json"))
+}
+
+pub fn load() -> Result<Registry, AppError> {
+    let path = registry_path()?;
+    if !path.exists() {
+        return Ok(Registry::default());
+    }
+
+    let text = fs::read_to_string(&path)
+        .map_err(|e| AppError::io("Failed to read install registry", e))?;
+    serde_json::from_str(&text)
+        .map_err(|e| AppError::validation(format!("Failed to parse install registry: {}", e)))
+}
+
+pub fn cleanup_staging() -> Result<usize, AppError> {
+    let mut registry = load()?;
+    if registry.staging.is_empty() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    registry.staging.retain(|dir| {
+        if staging::sweep(dir).is_err() {
+            return true;
+        }
+        removed += 1;
+        false
+    });
+    registry.save()?;
+    Ok(removed)
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Registry {
+    pub fn save(&self) -> Result<(), AppError> {
+        let path = registry_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::io("Failed to create app data directory", e))?;
+        }
+
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::io("Failed to serialize install registry", e))?;
+        fs::write(&path, text)
+            .map_err(|e| AppError::io("Failed to write install registry", e))
+    }
+
+    pub fn mark_staging(&mut self, dir: &Path) -> Result<(), AppError> {
+        if !self.staging.iter().any(|staging| staging == dir) {
+            self.staging.push(dir.to_path_buf());
+        }
+        self.save()
+    }
+
+    pub fn unmark_staging(&mut self, dir: &Path) -> Result<(), AppError> {
+        self.staging.retain(|staging| staging != dir);
+        self.save()
+    }
+
+    // Installs are keyed by version and binary type, re-installing replaces the old record
+    pub fn upsert(&mut self, record: InstallRecord) {
+        self.installs.retain(|install| {
+            !(install.version == record.version && install.binary_type == record.binary_type)
+        });
+        self.installs.push(record);
+    }
+
+    pub fn find(&self, version: &str, binary_type: Option<&str>) -> Result<&InstallRecord, AppError> {
+        let mut matches = self.installs.iter().filter(|install| {
+            install.version == version && binary_type.map_or(true, |kind| install.binary_type == kind)
+        });
+
+        let record = matches.next()
+            .ok_or_else(|| AppError::not_found(format!("{} is not installed", version)))?;
+        if matches.next().is_some() {
+            return Err(AppError::validation(format!("{} is installed for several binary types, please pick one", version)));
+        }
+        Ok(record)
+    }
+}
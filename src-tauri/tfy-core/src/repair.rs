@@ -0,0 +1,117 @@
+use crate::error::AppError;
+use crate::packages::{PackageEntry, PackageSource};
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Serialize, Default)]
+pub struct RepairReport {
+    pub packages_checked: usize,
+    pub packages_repaired: Vec<String>,
+    // Relative to the install folder, like the keys of tfy-index.json
+    pub damaged_files: Vec<String>,
+}
+
+// One file of a package as its zip directory lists it
+#[derive(Debug, PartialEq, Eq)]
+pub struct PackedFile {
+    pub name: String,
+    pub size: u64,
+    pub crc32: u32,
+}
+
+fn file_crc32(path: &Path) -> Option<u32> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Some(hasher.finalize())
+}
+
+const END_OF_DIRECTORY: &[u8] = b"PK\x05\x06";
+const DIRECTORY_ENTRY: &[u8] = b"PK\x01\x02";
+// The end of directory record is 22 bytes plus a comment of up to 64 KiB
+const MAX_END_OF_DIRECTORY: u64 = 22 + 0xFFFF;
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+// Where the zip directory starts, read from the end of directory record in the
+// last bytes of the zip. None for zip64 archives, which packages never are.
+pub fn directory_offset(tail: &[u8]) -> Option<u64> {
+    let record = (0..tail.len().saturating_sub(21)).rev()
+        .find(|at| tail[*at..].starts_with(END_OF_DIRECTORY))?;
+    u32_at(tail, record + 16).filter(|offset| *offset != u32::MAX).map(u64::from)
+}
+
+// The files in a zip directory, `directory` starts with its first entry
+pub fn parse_directory(name: &str, directory: &[u8]) -> Result<Vec<PackedFile>, AppError> {
+    let invalid = || AppError::archive(format!("Failed to read the file list of {}", name), "invalid zip directory");
+
+    let mut files = Vec::new();
+    let mut at = 0;
+    while directory.get(at..).is_some_and(|entry| entry.starts_with(DIRECTORY_ENTRY)) {
+        let crc32 = u32_at(directory, at + 16).ok_or_else(invalid)?;
+        let size = u32_at(directory, at + 24).ok_or_else(invalid)?;
+        let name_length = u16_at(directory, at + 28).ok_or_else(invalid)? as usize;
+        let extra_length = u16_at(directory, at + 30).ok_or_else(invalid)? as usize;
+        let comment_length = u16_at(directory, at + 32).ok_or_else(invalid)? as usize;
+        let file_name = directory.get(at + 46..at + 46 + name_length).ok_or_else(invalid)?;
+
+        let file_name = String::from_utf8_lossy(file_name).replace('\\', "/");
+        if !file_name.ends_with('/') {
+            files.push(PackedFile { name: file_name, size: u64::from(size), crc32 });
+        }
+        at += 46 + name_length + extra_length + comment_length;
+    }
+
+    Ok(files)
+}
+
+// Lists a package from the directory at the end of its zip, so checking an
+// install without tfy-index.json costs a small range request per package
+// instead of downloading all of them
+pub async fn list_package(source: &PackageSource<'_>, version: &str, package: &PackageEntry) -> Result<Vec<PackedFile>, AppError> {
+    let tail_start = package.packed_size.saturating_sub(MAX_END_OF_DIRECTORY);
+    let tail = source.fetch_tail(version, package, tail_start).await?;
+
+    let offset = directory_offset(&tail)
+        .ok_or_else(|| AppError::archive(format!("Failed to read the file list of {}", package.name), "no zip directory"))?;
+    if offset >= tail_start {
+        let start = (offset - tail_start) as usize;
+        return parse_directory(&package.name, tail.get(start..).unwrap_or_default());
+    }
+
+    // A big directory starts before the last 64 KiB
+    parse_directory(&package.name, &source.fetch_tail(version, package, offset).await?)
+}
+
+// Compares each file of a package against the size and CRC-32 in its zip
+// directory. Returns the paths that are missing or differ, relative to
+// `install_dir` like the keys of tfy-index.json.
+pub fn find_damaged(files: &[PackedFile], install_dir: &Path, extract_root: &str) -> Vec<String> {
+    let mut damaged = Vec::new();
+    for file in files {
+        let relative = format!("{}{}", extract_root, file.name);
+        let path = install_dir.join(&relative);
+        let intact = fs::metadata(&path).is_ok_and(|metadata| metadata.len() == file.size)
+            && file_crc32(&path) == Some(file.crc32);
+
+        if !intact {
+            damaged.push(relative);
+        }
+    }
+    damaged
+}
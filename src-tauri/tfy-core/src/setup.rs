@@ -0,0 +1,297 @@
+use crate::apps::DownloadRequest;
+use crate::catalog::CatalogApp;
+use crate::config::{self, RunMode};
+use crate::downloads::DownloadManager;
+use crate::error::AppError;
+use crate::process::command;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Runs catalog installers unattended. An app's `installer` kind says which
+// silent flags its setup understands and what its exit codes mean, its
+// `silent_args` replace those flags when the vendor wants something else.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallerKind {
+    Nsis,
+    // Inno Setup
+    Inno,
+    // Windows Installer package, run through msiexec
+    Msi,
+    // Squirrel.Windows, e.g. Discord. Installs per user and needs no admin.
+    Squirrel,
+    // A plain .exe that is copied into place rather than installed
+    Portable,
+}
+
+impl InstallerKind {
+    pub fn default_args(self) -> &'static [&'static str] {
+        match self {
+            InstallerKind::Nsis => &["/S"],
+            InstallerKind::Inno => &["/VERYSILENT", "/SUPPRESSMSGBOXES", "/NORESTART", "/SP-"],
+            InstallerKind::Msi => &["/qn", "/norestart"],
+            InstallerKind::Squirrel => &["--silent"],
+            InstallerKind::Portable => &[],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallOutcome {
+    Installed,
+    // Installed, but Windows has to restart before it is usable
+    RebootRequired,
+    Failed,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct InstallResult {
+    pub app_id: String,
+    pub outcome: InstallOutcome,
+    // None for portable apps and installers that were killed
+    pub exit_code: Option<i32>,
+    pub message: String,
+    // The installer that ran, or where a portable app was copied to
+    pub path: PathBuf,
+}
+
+// What an exit code of `kind` means, with the reason for failures. Any
+// installer can ask for a restart through the Windows Installer codes.
+pub fn outcome(kind: Option<InstallerKind>, code: Option<i32>) -> (InstallOutcome, &'static str) {
+    use InstallOutcome::*;
+
+    match (kind, code) {
+        (_, None) => (Failed, "was stopped before it finished"),
+        (_, Some(0)) => (Installed, "installed"),
+        // ERROR_SUCCESS_REBOOT_REQUIRED and ERROR_SUCCESS_REBOOT_INITIATED
+        (_, Some(3010)) | (_, Some(1641)) => (RebootRequired, "installed, restart Windows to finish"),
+        (Some(InstallerKind::Msi), Some(1602)) => (Failed, "was cancelled"),
+        (Some(InstallerKind::Msi), Some(1603)) => (Failed, "failed with a fatal error"),
+        (Some(InstallerKind::Msi), Some(1618)) => (Failed, "could not start, another installation is in progress"),
+        (Some(InstallerKind::Msi), Some(1625)) => (Failed, "is blocked by system policy"),
+        (Some(InstallerKind::Inno), Some(2)) | (Some(InstallerKind::Inno), Some(5)) => (Failed, "was cancelled"),
+        // Inno's "Preparing to Install" found a pending restart, nothing was installed
+        (Some(InstallerKind::Inno), Some(8)) => (Failed, "needs Windows to restart before it can install"),
+        (Some(InstallerKind::Nsis), Some(1)) => (Failed, "was cancelled"),
+        (Some(InstallerKind::Nsis), Some(2)) => (Failed, "was aborted by the installer"),
+        (_, Some(_)) => (Failed, "failed"),
+    }
+}
+
+// The declared kind, or Msi for .msi files that don't declare one
+pub fn kind_of(app: &CatalogApp, installer: &Path) -> Option<InstallerKind> {
+    let is_msi = installer.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("msi"));
+    app.installer.or(is_msi.then_some(InstallerKind::Msi))
+}
+
+// The program and arguments that install `installer` without any dialogs
+pub fn silent_command(app: &CatalogApp, installer: &Path) -> Result<(PathBuf, Vec<String>), AppError> {
+    let kind = kind_of(app, installer);
+    let mut args: Vec<String> = if app.silent_args.is_empty() {
+        kind.map(|kind| kind.default_args()).unwrap_or_default().iter().map(|arg| arg.to_string()).collect()
+    } else {
+        app.silent_args.clone()
+    };
+
+    match kind {
+        Some(InstallerKind::Portable) => Err(AppError::validation(format!("{} is portable, there is nothing to run", app.name))),
+        Some(InstallerKind::Msi) => {
+            args.splice(0..0, ["/i".to_string(), installer.display().to_string()]);
+            Ok((PathBuf::from("msiexec"), args))
+        }
+        None if args.is_empty() => Err(AppError::validation(format!(
+            "{} has no unattended install, download it and run it instead",
+            app.name
+        ))),
+        _ => Ok((installer.to_path_buf(), args)),
+    }
+}
+
+// Asked before an installer runs while run_mode is Ask, with a title and the
+// details to decide on. The GUI shows a dialog, the CLI asks on the terminal.
+pub trait Confirm: Send + Sync {
+    fn confirm(&self, title: &str, message: &str) -> bool;
+}
+
+impl<F: Fn(&str, &str) -> bool + Send + Sync> Confirm for F {
+    fn confirm(&self, title: &str, message: &str) -> bool {
+        self(title, message)
+    }
+}
+
+// For callers that already asked, e.g. once for a whole profile
+pub struct Confirmed;
+
+impl Confirm for Confirmed {
+    fn confirm(&self, _title: &str, _message: &str) -> bool {
+        true
+    }
+}
+
+// Whether to go ahead: always with run_mode Auto, otherwise when `confirm` says
+// so. A config that can't be read counts as Ask.
+pub fn confirmed(confirm: &dyn Confirm, title: &str, message: &str) -> bool {
+    let run_mode = config::load().map(|config| config.downloads.run_mode).unwrap_or_default();
+    run_mode == RunMode::Auto || confirm.confirm(title, message)
+}
+
+// Whether install() gets through `app` with nobody at the keyboard. Judged by
+// its catalog filename, the download isn't there yet.
+pub fn is_unattended(app: &CatalogApp) -> bool {
+    let installer = Path::new(&app.filename);
+    kind_of(app, installer) == Some(InstallerKind::Portable) || silent_command(app, installer).is_ok()
+}
+
+// Per user, like Squirrel apps: %LOCALAPPDATA%\Programs\tfy-tool
+pub fn portable_dir() -> Result<PathBuf, AppError> {
+    let local_dir = dirs::data_local_dir()
+        .ok_or_else(|| AppError::not_found("Could not find the local app data directory"))?;
+    Ok(local_dir.join("Programs").join("tfy-tool"))
+}
+
+// Installs `app` from its downloaded `installer` and waits for it. An installer
+// that runs and fails is an Ok result with a Failed outcome, errors are for
+// installers that could not be started at all.
+pub async fn install(app: &CatalogApp, installer: &Path) -> Result<InstallResult, AppError> {
+    if kind_of(app, installer) == Some(InstallerKind::Portable) {
+        return install_portable(app, installer);
+    }
+
+    let (program, args) = silent_command(app, installer)?;
+    let label = app.name.clone();
+    let elevate = kind_of(app, installer) == Some(InstallerKind::Msi);
+    let code = tokio::task::spawn_blocking(move || run_and_wait(&program, &args, &label, elevate))
+        .await
+        .map_err(|e| AppError::io(format!("Failed to wait for the {} installer", app.name), e))??;
+
+    let (outcome, reason) = outcome(kind_of(app, installer), code);
+    let message = match (outcome, code) {
+        (InstallOutcome::Failed, Some(code)) => format!("{} {} (exit code {})", app.name, reason, code),
+        _ => format!("{} {}", app.name, reason),
+    };
+
+    Ok(InstallResult {
+        app_id: app.id.clone(),
+        outcome,
+        exit_code: code,
+        message,
+        path: installer.to_path_buf(),
+    })
+}
+
+// Downloads `app` into `dir` through the queue and installs it once the
+// download's details are confirmed, see confirmed()
+pub async fn install_app(
+    downloads: &DownloadManager,
+    app: &CatalogApp,
+    dir: &Path,
+    confirm: &dyn Confirm,
+) -> Result<InstallResult, AppError> {
+    let queued = downloads.enqueue(DownloadRequest::for_app(app, dir))?;
+    let download = downloads.wait(queued.id).await?;
+
+    let message = format!(
+        "{}\n\n{}\n\nInstall it now?",
+        download.path.display(),
+        download.summary.as_deref().unwrap_or_default()
+    );
+    if !confirmed(confirm, &format!("Install {}?", app.name), &message) {
+        return Err(AppError::cancelled(format!("Install of {} was cancelled", app.name)));
+    }
+    install_download(downloads, app, download.id).await
+}
+
+// Installs `app` from download `id` after the caller had it confirmed, like
+// DownloadManager::run(). The file is checked against its download hash
+// right before it runs.
+pub async fn install_download(downloads: &DownloadManager, app: &CatalogApp, id: u64) -> Result<InstallResult, AppError> {
+    let (_, file) = downloads.verified_file(id)?;
+    install(app, &file.path).await
+}
+
+fn install_portable(app: &CatalogApp, file: &Path) -> Result<InstallResult, AppError> {
+    let dir = portable_dir()?.join(&app.id);
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::io(format!("Failed to create {}", dir.display()), e))?;
+
+    let target = dir.join(file.file_name().unwrap_or_default());
+    fs::copy(file, &target)
+        .map_err(|e| AppError::io(format!("Failed to copy {} to {}", app.name, target.display()), e))?;
+
+    Ok(InstallResult {
+        app_id: app.id.clone(),
+        outcome: InstallOutcome::Installed,
+        exit_code: None,
+        message: format!("{} copied to {}", app.name, target.display()),
+        path: target,
+    })
+}
+
+// Blocks until the installer exits and returns its exit code. msiexec has no
+// manifest asking for admin and a quiet per-machine install just fails with
+// 1603, so `elevate` goes through UAC up front instead of waiting for a 740.
+fn run_and_wait(program: &Path, args: &[String], label: &str, elevate: bool) -> Result<Option<i32>, AppError> {
+    let started = |mut process: Command| process.status().map(|status| status.code());
+
+    let result = if elevate {
+        started(elevated(program, args))
+    } else {
+        let mut process = command(program);
+        process.args(args);
+        match started(process) {
+            // ERROR_ELEVATION_REQUIRED: the installer's manifest asks for admin
+            Err(e) if e.raw_os_error() == Some(740) => started(elevated(program, args)),
+            result => result,
+        }
+    };
+    result.map_err(|e| AppError::io(format!("Failed to start the {} installer", label), e))
+}
+
+// Starts `program` through a UAC prompt and still passes its exit code back
+fn elevated(program: &Path, args: &[String]) -> Command {
+    let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+    let arguments = args.iter().map(|arg| windows_quote(arg)).collect::<Vec<_>>().join(" ");
+
+    let mut script = format!("$p = Start-Process -FilePath {} -Verb RunAs -Wait -PassThru", quote(&program.display().to_string()));
+    if !arguments.is_empty() {
+        script.push_str(&format!(" -ArgumentList {}", quote(&arguments)));
+    }
+    script.push_str("; exit $p.ExitCode");
+
+    let mut process = command("powershell");
+    process.args(["-NoProfile", "-NonInteractive", "-Command", &script]);
+    process
+}
+
+// Quotes one argument the way CommandLineToArgvW splits them
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                backslashes = 0;
+            }
+        }
+        if c != '\\' {
+            quoted.push(c);
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
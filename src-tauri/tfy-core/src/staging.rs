@@ -0,0 +1,163 @@
+use crate::error::AppError;
+use fs2::FileExt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Files that belong to the user rather than to a package, carried over on re-install
+const USER_FILES: [&str; 2] = ["AppSettings.xml", "ClientSettings"];
+
+// Staging sits next to the final folder so the rename never crosses volumes
+pub fn staging_dir(output_dir: &Path) -> PathBuf {
+    sibling(output_dir, "staging")
+}
+
+// Where commit() moves the old install while the new one takes its place
+pub fn previous_dir(output_dir: &Path) -> PathBuf {
+    sibling(output_dir, "old")
+}
+
+// Held by the process installing into `output_dir` until the install is
+// committed or rolled back. sweep() leaves the staging and previous directories
+// alone while it is, they belong to an install that is still running.
+pub struct InstallLock {
+    file: fs::File,
+    path: PathBuf,
+}
+
+impl Drop for InstallLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn try_lock(output_dir: &Path) -> Result<Option<InstallLock>, AppError> {
+    let path = sibling(output_dir, "lock");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io(format!("Failed to create {}", parent.display()), e))?;
+    }
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&path)
+        .map_err(|e| AppError::io(format!("Failed to open {}", path.display()), e))?;
+
+    match FileExt::try_lock_exclusive(&file) {
+        Ok(()) => Ok(Some(InstallLock { file, path })),
+        Err(_) => Ok(None),
+    }
+}
+
+pub fn lock(output_dir: &Path) -> Result<InstallLock, AppError> {
+    try_lock(output_dir)?.ok_or_else(|| {
+        AppError::validation(format!("{} is already being installed by another process", output_dir.display()))
+    })
+}
+
+fn sibling(output_dir: &Path, suffix: &str) -> PathBuf {
+    let name = output_dir.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    output_dir.with_file_name(format!(".{}.{}", name, suffix))
+}
+
+pub fn prepare(staging: &Path, output_dir: &Path) -> Result<(), AppError> {
+    if staging.exists() {
+        fs::remove_dir_all(staging)
+            .map_err(|e| AppError::io(format!("Failed to clear staging directory {}", staging.display()), e))?;
+    }
+    fs::create_dir_all(staging)
+        .map_err(|e| AppError::io(format!("Failed to create staging directory {}", staging.display()), e))?;
+
+    for name in USER_FILES {
+        let existing = output_dir.join(name);
+        if existing.exists() {
+            copy_recursive(&existing, &staging.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<(), AppError> {
+    if from.is_dir() {
+        fs::create_dir_all(to)
+            .map_err(|e| AppError::io(format!("Failed to create {}", to.display()), e))?;
+        let entries = fs::read_dir(from)
+            .map_err(|e| AppError::io(format!("Failed to read directory {}", from.display()), e))?;
+        for entry in entries {
+            let entry = entry
+                .map_err(|e| AppError::io(format!("Failed to read directory {}", from.display()), e))?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to)
+            .map(|_| ())
+            .map_err(|e| AppError::io(format!("Failed to copy {}", from.display()), e))
+    }
+}
+
+// Swaps the finished staging directory into place, the old folder is only removed once that worked
+pub fn commit(staging: &Path, output_dir: &Path) -> Result<(), AppError> {
+    if !output_dir.exists() {
+        return fs::rename(staging, output_dir)
+            .map_err(|e| AppError::io(format!("Failed to move {} into place", staging.display()), e));
+    }
+
+    let previous = previous_dir(output_dir);
+    if previous.exists() {
+        fs::remove_dir_all(&previous)
+            .map_err(|e| AppError::io(format!("Failed to remove {}", previous.display()), e))?;
+    }
+
+    fs::rename(output_dir, &previous)
+        .map_err(|e| AppError::io(format!("Failed to move {} aside", output_dir.display()), e))?;
+
+    if let Err(e) = fs::rename(staging, output_dir) {
+        let message = format!("Failed to move {} into place", staging.display());
+        return Err(match fs::rename(&previous, output_dir) {
+            Ok(()) => AppError::io(message, e),
+            // The install is still in `previous`, sweep() moves it back later
+            Err(rollback) => AppError::io(
+                message,
+                format!("{}. Moving the old install back failed too, it is in {}: {}", e, previous.display(), rollback),
+            ),
+        });
+    }
+
+    let _ = fs::remove_dir_all(&previous);
+    Ok(())
+}
+
+pub fn discard(staging: &Path) {
+    let _ = fs::remove_dir_all(staging);
+}
+
+// Cleans up a staging or previous directory left behind by an install that
+// didn't finish. A previous directory whose install is missing is moved back
+// instead, commit() stopped between its two renames then. Fails without
+// touching anything while another process holds the install's lock.
+pub fn sweep(dir: &Path) -> Result<(), AppError> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    let name = dir.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let (output_name, suffix) = name.strip_prefix('.')
+        .and_then(|name| name.rsplit_once('.'))
+        .filter(|(_, suffix)| *suffix == "staging" || *suffix == "old")
+        .ok_or_else(|| AppError::validation(format!("{} is not a staging directory", dir.display())))?;
+    let output_dir = dir.with_file_name(output_name);
+    let Some(_lock) = try_lock(&output_dir)? else {
+        return Err(AppError::validation(format!("{} is in use by a running install", dir.display())));
+    };
+
+    let restore_to = Some(output_dir).filter(|output_dir| suffix == "old" && !output_dir.exists());
+
+    match restore_to {
+        Some(output_dir) => fs::rename(dir, &output_dir)
+            .map_err(|e| AppError::io(format!("Failed to move {} back", dir.display()), e)),
+        None => fs::remove_dir_all(dir)
+            .map_err(|e| AppError::io(format!("Failed to remove {}", dir.display()), e)),
+    }
+}
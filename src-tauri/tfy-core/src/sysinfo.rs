@@ -0,0 +1,75 @@
+use crate::process::command;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct SystemInfo {
+    pub os: String,
+    pub cpu: String,
+    pub ram: String,
+    pub gpu: String,
+}
+
+fn query(program: &str, args: &[&str]) -> Option<String> {
+    let output = command(program).args(args).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Every field falls back to a placeholder when the query fails, so this never errors
+pub fn system_info() -> SystemInfo {
+    let mut system_info = SystemInfo {
+        os: "Windows 11".to_string(),
+        cpu: "Unknown Processor".to_string(),
+        ram: "Memory information unavailable".to_string(),
+        gpu: "Graphics information unavailable".to_string(),
+    };
+
+    // Get OS information
+    if let Some(os_output) = query("cmd", &["/C", "ver"]) {
+        if os_output.contains("10.0") {
+            if os_output.contains("22000") || os_output.contains("22621") || os_output.contains("22631") {
+                system_info.os = "Windows 11".to_string();
+            } else {
+                system_info.os = "Windows 10".to_string();
+            }
+        }
+    }
+
+    // Get CPU information
+    if let Some(cpu_output) = query("wmic", &["cpu", "get", "name", "/format:value"]) {
+        for line in cpu_output.lines() {
+            if let Some(cpu_name) = line.strip_prefix("Name=").map(str::trim) {
+                if !cpu_name.is_empty() {
+                    system_info.cpu = cpu_name.to_string();
+                    break;
+                }
+            }
+        }
+    }
+
+    // Get RAM information
+    if let Some(ram_output) = query("wmic", &["computersystem", "get", "TotalPhysicalMemory", "/format:value"]) {
+        for line in ram_output.lines() {
+            if let Some(value) = line.strip_prefix("TotalPhysicalMemory=") {
+                if let Ok(bytes) = value.trim().parse::<u64>() {
+                    let gb = bytes / (1024 * 1024 * 1024);
+                    system_info.ram = format!("{} GB RAM", gb);
+                    break;
+                }
+            }
+        }
+    }
+
+    // Get GPU information
+    if let Some(gpu_output) = query("wmic", &["path", "win32_VideoController", "get", "name", "/format:value"]) {
+        for line in gpu_output.lines() {
+            if let Some(gpu_name) = line.strip_prefix("Name=").map(str::trim) {
+                if !gpu_name.is_empty() && !gpu_name.contains("Microsoft") {
+                    system_info.gpu = gpu_name.to_string();
+                    break;
+                }
+            }
+        }
+    }
+
+    system_info
+}
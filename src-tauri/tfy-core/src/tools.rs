@@ -0,0 +1,223 @@
+use crate::apps::DownloadedFile;
+use crate::error::AppError;
+use crate::http::HttpClient;
+use crate::packages;
+use crate::process::command;
+use std::fs;
+use zip::ZipArchive;
+
+// The one-click tools on the Tools page, looked up by name
+pub async fn run_function(client: &dyn HttpClient, name: &str) -> Result<String, AppError> {
+    match name {
+        "winrar_crack" => winrar_crack(client).await,
+        "wifi_passwords" => wifi_passwords().await,
+        "activate_windows" => activate_windows().await,
+        "run_optimization" => run_optimization(client).await,
+        "clean_temp" => clean_temp().await,
+        "install_atlas_tools" => {
+            let wizard = download_atlas_tools(client).await?;
+            Ok(format!("✅ Atlas tools downloaded, AME Wizard is ready to run from {}", wizard.path.display()))
+        }
+        _ => Err(AppError::not_found(format!("Unknown function: {}", name)))
+    }
+}
+
+async fn winrar_crack(client: &dyn HttpClient) -> Result<String, AppError> {
+    let key_content = client.get("https://github.com/jtlw99/crack-winrar/releases/download/v1/rarreg.key", None)
+        .await?
+        .error_for_status("rarreg.key")?
+        .bytes()
+        .await?;
+    
+    let paths = [
+        "C:\\Program Files\\WinRAR\\rarreg.key",
+        "C:\\Program Files (x86)\\WinRAR\\rarreg.key"
+    ];
+    
+    for path in &paths {
+        if fs::write(path, &key_content).is_ok() {
+            return Ok(format!("✅ WinRAR crack applied successfully to {}", path));
+        }
+    }
+    
+    Err(AppError::not_found("No valid WinRAR installation found"))
+}
+
+async fn wifi_passwords() -> Result<String, AppError> {
+    let output = command("cmd")
+        .args(["/C", "netsh wlan show profiles"])
+        .output()
+        .map_err(|e| AppError::io("Failed to get WiFi profiles", e))?;
+    
+    let profiles_output = String::from_utf8_lossy(&output.stdout);
+    let mut passwords = String::new();
+    
+    for line in profiles_output.lines() {
+        if line.contains("All User Profile") {
+            if let Some(profile_name) = line.split(':').nth(1) {
+                let profile_name = profile_name.trim();
+                
+                let password_output = command("cmd")
+                    .args(["/C", &format!("netsh wlan show profile name=\"{}\" key=clear", profile_name)])
+                    .output();
+                
+                if let Ok(pwd_output) = password_output {
+                    let pwd_text = String::from_utf8_lossy(&pwd_output.stdout);
+                    for pwd_line in pwd_text.lines() {
+                        if pwd_line.contains("Key Content") {
+                            if let Some(password) = pwd_line.split(':').nth(1) {
+                                passwords.push_str(&format!("{}: {}\n", profile_name, password.trim()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    
+    if passwords.is_empty() {
+        Ok("No WiFi passwords found.".to_string())
+    } else {
+        // Create a temporary file with the passwords and open it in notepad
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("wifi_passwords.txt");
+        fs::write(&temp_file, &passwords)
+            .map_err(|e| AppError::io("Failed to write passwords file", e))?;
+        
+        // Open the file in notepad
+        command("notepad")
+            .arg(&temp_file)
+            .spawn()
+            .map_err(|e| AppError::io("Failed to open notepad", e))?;
+        
+        Ok("✅ WiFi passwords saved and opened in Notepad!".to_string())
+    }
+}
+
+async fn activate_windows() -> Result<String, AppError> {
+    let status = command("powershell")
+        .args(["-Command", "irm https://get.activated.win | iex"])
+        .status()
+        .map_err(|e| AppError::io("Failed to run activation", e))?;
+    
+    if status.success() {
+        Ok("✅ Windows activation process started!".to_string())
+    } else {
+        Err(AppError::validation("Failed to start Windows activation"))
+    }
+}
+
+async fn run_optimization(client: &dyn HttpClient) -> Result<String, AppError> {
+    let bat_content = client.get("https://raw.githubusercontent.com/DragosKissLove/testbot/main/TFY%20Optimization.bat", None)
+        .await?
+        .error_for_status("optimization script")?
+        .text()
+        .await?;
+    
+    let temp_dir = std::env::temp_dir();
+    let temp_path = temp_dir.join("TFY_Optimization.bat");
+    
+    fs::write(&temp_path, bat_content)
+        .map_err(|e| AppError::io("Failed to write script", e))?;
+    
+    let status = command("powershell")
+        .args(["-Command", &format!("Start-Process '{}' -Verb RunAs", temp_path.display())])
+        .status()
+        .map_err(|e| AppError::io("Failed to run optimization", e))?;
+    
+    if status.success() {
+        Ok("✅ TFY Optimization started successfully!".to_string())
+    } else {
+        Err(AppError::validation("Failed to start optimization"))
+    }
+}
+
+async fn clean_temp() -> Result<String, AppError> {
+    let status = command("cmd")
+        .args(["/C", "del /s /f /q %temp%\\* && del /s /f /q C:\\Windows\\Temp\\*"])
+        .status()
+        .map_err(|e| AppError::io("Failed to clean temp files", e))?;
+    
+    if status.success() {
+        Ok("✅ Temporary files cleaned successfully!".to_string())
+    } else {
+        Err(AppError::validation("Failed to clean temporary files"))
+    }
+}
+
+pub const AME_WIZARD_URL: &str = "https://download.ameliorated.io/AME%20Wizard%20Beta.zip";
+
+// Saves the Atlas playbook and unpacks AME Wizard into Downloads. Returns the
+// wizard's executable without starting it, see DownloadManager::run().
+pub async fn download_atlas_tools(client: &dyn HttpClient) -> Result<DownloadedFile, AppError> {
+    let downloads_dir = dirs::download_dir()
+        .ok_or_else(|| AppError::not_found("Could not find Downloads directory"))?;
+    
+    // Download Atlas Playbook
+    let atlas_url = "https://github.com/Atlas-OS/Atlas/releases/download/0.4.1/AtlasPlaybook_v0.4.1.apbx";
+    let atlas_bytes = client.get(atlas_url, None)
+        .await?
+        .error_for_status("Atlas Playbook")?
+        .bytes()
+        .await?;
+    
+    let atlas_path = downloads_dir.join("AtlasPlaybook_v0.4.1.apbx");
+    fs::write(&atlas_path, atlas_bytes)
+        .map_err(|e| AppError::io("Failed to save Atlas Playbook", e))?;
+    
+    // Download AME Wizard
+    let ame_bytes = client.get(AME_WIZARD_URL, None)
+        .await?
+        .error_for_status("AME Wizard")?
+        .bytes()
+        .await?;
+    
+    let ame_zip_path = downloads_dir.join("AME_Wizard_Beta.zip");
+    fs::write(&ame_zip_path, ame_bytes)
+        .map_err(|e| AppError::io("Failed to save AME Wizard", e))?;
+    
+    // Extract AME Wizard
+    let ame_extract_dir = downloads_dir.join("AME_Wizard_Beta");
+    fs::create_dir_all(&ame_extract_dir)
+        .map_err(|e| AppError::io("Failed to create extraction directory", e))?;
+    
+    let file = fs::File::open(&ame_zip_path)
+        .map_err(|e| AppError::io("Failed to open AME zip", e))?;
+    
+    let mut wizard = None;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| AppError::archive("Failed to read AME zip", e))?;
+    
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)
+            .map_err(|e| AppError::archive("Failed to read file from AME zip", e))?;
+        
+        if file.is_dir() {
+            continue;
+        }
+        
+        // Entries that would land outside the extraction folder are skipped
+        let Some(entry) = packages::entry_path(&file) else {
+            continue;
+        };
+        let file_path = ame_extract_dir.join(entry);
+        
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::io("Failed to create directory", e))?;
+        }
+        
+        let mut output_file = fs::File::create(&file_path)
+            .map_err(|e| AppError::io("Failed to create extracted file", e))?;
+        
+        std::io::copy(&mut file, &mut output_file)
+            .map_err(|e| AppError::archive("Failed to extract file", e))?;
+        
+        if wizard.is_none() && file_path.extension().and_then(|s| s.to_str()) == Some("exe") {
+            wizard = Some(file_path);
+        }
+    }
+    
+    let wizard = wizard.ok_or_else(|| AppError::not_found("AME Wizard zip has no executable"))?;
+    DownloadedFile::read(&wizard)
+}
@@ -0,0 +1,176 @@
+use crate::error::AppError;
+use crate::http::HttpClient;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub const DEFAULT_CHANNEL: &str = "LIVE";
+pub const DEFAULT_BINARY_TYPE: &str = "WindowsPlayer";
+pub const SAVED_VERSIONS_URL: &str = "https://pastebin.com/raw/vgqfphAY";
+
+#[derive(Serialize, Debug)]
+pub struct VersionInfo {
+    pub hash: String,
+    pub description: String,
+}
+
+pub struct BinaryConfig {
+    pub blob_dir: String,
+    pub version_file: String,
+}
+
+// `url` serves a JSON object of version hash to description, normally SAVED_VERSIONS_URL
+pub async fn fetch_saved_versions(client: &dyn HttpClient, url: &str) -> Result<Vec<VersionInfo>, AppError> {
+    let json_text = client.get(url, Some(std::time::Duration::from_secs(10)))
+        .await?
+        .error_for_status("saved versions")?
+        .text()
+        .await?;
+
+    let data: serde_json::Value = serde_json::from_str(&json_text)
+        .map_err(|e| AppError::validation(format!("Failed to parse JSON: {}", e)))?;
+
+    let mut versions = Vec::new();
+    if let Value::Object(map) = data {
+        for (full_ver, description) in map {
+            let hash = if full_ver.to_lowercase().starts_with("version-") {
+                full_ver[8..].to_string()
+            } else {
+                full_ver
+            };
+            
+            let desc = description.as_str().unwrap_or("").to_string();
+            versions.push(VersionInfo { hash, description: desc });
+        }
+    }
+
+    versions.sort_by(|a, b| a.hash.cmp(&b.hash));
+    Ok(versions)
+}
+
+pub fn cdn_base_url(channel: &str) -> String {
+    if channel.to_uppercase() == "LIVE" {
+        "https://setup.rbxcdn.com".to_string()
+    } else {
+        format!("https://setup.rbxcdn.com/channel/{}", channel.to_lowercase())
+    }
+}
+
+pub fn normalize_version(version_hash: &str) -> String {
+    if version_hash.to_lowercase().starts_with("version-") {
+        version_hash.to_lowercase()
+    } else {
+        format!("version-{}", version_hash.to_lowercase())
+    }
+}
+
+pub fn binary_config(binary_type: &str) -> Result<BinaryConfig, AppError> {
+    get_binary_configs()
+        .remove(binary_type)
+        .ok_or_else(|| AppError::validation(format!("Unknown binary type: {}", binary_type)))
+}
+
+fn get_binary_configs() -> HashMap<String, BinaryConfig> {
+    let mut configs = HashMap::new();
+    
+    configs.insert("WindowsPlayer".to_string(), BinaryConfig {
+        blob_dir: "/".to_string(),
+        version_file: "/version".to_string(),
+    });
+    
+    configs.insert("WindowsStudio64".to_string(), BinaryConfig {
+        blob_dir: "/".to_string(),
+        version_file: "/versionQTStudio".to_string(),
+    });
+    
+    configs.insert("MacPlayer".to_string(), BinaryConfig {
+        blob_dir: "/mac/".to_string(),
+        version_file: "/mac/version".to_string(),
+    });
+    
+    configs.insert("MacStudio".to_string(), BinaryConfig {
+        blob_dir: "/mac/".to_string(),
+        version_file: "/mac/versionStudio".to_string(),
+    });
+    
+    configs
+}
+
+// Where each package of a version is unpacked, relative to the install folder
+pub fn extract_roots(binary_type: &str) -> HashMap<String, String> {
+    if binary_type.contains("Player") {
+        get_player_extract_roots()
+    } else {
+        get_studio_extract_roots()
+    }
+}
+
+fn get_player_extract_roots() -> HashMap<String, String> {
+    let mut roots = HashMap::new();
+    
+    roots.insert("RobloxApp.zip".to_string(), "".to_string());
+    roots.insert("redist.zip".to_string(), "".to_string());
+    roots.insert("shaders.zip".to_string(), "shaders/".to_string());
+    roots.insert("ssl.zip".to_string(), "ssl/".to_string());
+    roots.insert("WebView2.zip".to_string(), "".to_string());
+    roots.insert("WebView2RuntimeInstaller.zip".to_string(), "WebView2RuntimeInstaller/".to_string());
+    roots.insert("content-avatar.zip".to_string(), "content/avatar/".to_string());
+    roots.insert("content-configs.zip".to_string(), "content/configs/".to_string());
+    roots.insert("content-fonts.zip".to_string(), "content/fonts/".to_string());
+    roots.insert("content-sky.zip".to_string(), "content/sky/".to_string());
+    roots.insert("content-sounds.zip".to_string(), "content/sounds/".to_string());
+    roots.insert("content-textures2.zip".to_string(), "content/textures/".to_string());
+    roots.insert("content-models.zip".to_string(), "content/models/".to_string());
+    roots.insert("content-platform-fonts.zip".to_string(), "PlatformContent/pc/fonts/".to_string());
+    roots.insert("content-platform-dictionaries.zip".to_string(), "PlatformContent/pc/shared_compression_dictionaries/".to_string());
+    roots.insert("content-terrain.zip".to_string(), "PlatformContent/pc/terrain/".to_string());
+    roots.insert("content-textures3.zip".to_string(), "PlatformContent/pc/textures/".to_string());
+    roots.insert("extracontent-luapackages.zip".to_string(), "ExtraContent/LuaPackages/".to_string());
+    roots.insert("extracontent-translations.zip".to_string(), "ExtraContent/translations/".to_string());
+    roots.insert("extracontent-models.zip".to_string(), "ExtraContent/models/".to_string());
+    roots.insert("extracontent-textures.zip".to_string(), "ExtraContent/textures/".to_string());
+    roots.insert("extracontent-places.zip".to_string(), "ExtraContent/places/".to_string());
+    
+    roots
+}
+
+fn get_studio_extract_roots() -> HashMap<String, String> {
+    let mut roots = HashMap::new();
+    
+    roots.insert("RobloxStudio.zip".to_string(), "".to_string());
+    roots.insert("RibbonConfig.zip".to_string(), "RibbonConfig/".to_string());
+    roots.insert("redist.zip".to_string(), "".to_string());
+    roots.insert("Libraries.zip".to_string(), "".to_string());
+    roots.insert("LibrariesQt5.zip".to_string(), "".to_string());
+    roots.insert("WebView2.zip".to_string(), "".to_string());
+    roots.insert("WebView2RuntimeInstaller.zip".to_string(), "WebView2RuntimeInstaller/".to_string());
+    roots.insert("shaders.zip".to_string(), "shaders/".to_string());
+    roots.insert("ssl.zip".to_string(), "ssl/".to_string());
+    roots.insert("Qml.zip".to_string(), "Qml/".to_string());
+    roots.insert("Plugins.zip".to_string(), "Plugins/".to_string());
+    roots.insert("StudioFonts.zip".to_string(), "StudioFonts/".to_string());
+    roots.insert("BuiltInPlugins.zip".to_string(), "BuiltInPlugins/".to_string());
+    roots.insert("ApplicationConfig.zip".to_string(), "ApplicationConfig/".to_string());
+    roots.insert("BuiltInStandalonePlugins.zip".to_string(), "BuiltInStandalonePlugins/".to_string());
+    roots.insert("content-qt_translations.zip".to_string(), "content/qt_translations/".to_string());
+    roots.insert("content-sky.zip".to_string(), "content/sky/".to_string());
+    roots.insert("content-fonts.zip".to_string(), "content/fonts/".to_string());
+    roots.insert("content-avatar.zip".to_string(), "content/avatar/".to_string());
+    roots.insert("content-models.zip".to_string(), "content/models/".to_string());
+    roots.insert("content-sounds.zip".to_string(), "content/sounds/".to_string());
+    roots.insert("content-configs.zip".to_string(), "content/configs/".to_string());
+    roots.insert("content-api-docs.zip".to_string(), "content/api_docs/".to_string());
+    roots.insert("content-textures2.zip".to_string(), "content/textures/".to_string());
+    roots.insert("content-studio_svg_textures.zip".to_string(), "content/studio_svg_textures/".to_string());
+    roots.insert("content-platform-fonts.zip".to_string(), "PlatformContent/pc/fonts/".to_string());
+    roots.insert("content-platform-dictionaries.zip".to_string(), "PlatformContent/pc/shared_compression_dictionaries/".to_string());
+    roots.insert("content-terrain.zip".to_string(), "PlatformContent/pc/terrain/".to_string());
+    roots.insert("content-textures3.zip".to_string(), "PlatformContent/pc/textures/".to_string());
+    roots.insert("extracontent-translations.zip".to_string(), "ExtraContent/translations/".to_string());
+    roots.insert("extracontent-luapackages.zip".to_string(), "ExtraContent/LuaPackages/".to_string());
+    roots.insert("extracontent-textures.zip".to_string(), "ExtraContent/textures/".to_string());
+    roots.insert("extracontent-scripts.zip".to_string(), "ExtraContent/scripts/".to_string());
+    roots.insert("extracontent-models.zip".to_string(), "ExtraContent/models/".to_string());
+    
+    roots
+}
@@ -0,0 +1,59 @@
+use tfy_core::bisect::{self, BisectSession};
+
+fn history() -> Vec<String> {
+    (0..9).map(|i| format!("version-{}", i)).collect()
+}
+
+fn session(good: &str, bad: &str) -> BisectSession {
+    BisectSession::start(&history(), good, bad, "LIVE".to_string(), "WindowsPlayer".to_string()).unwrap()
+}
+
+#[test]
+fn verdicts_narrow_down_to_the_first_bad_version() {
+    let mut session = session("version-1", "version-7");
+    let status = session.status();
+    assert_eq!((status.last_good.as_str(), status.first_bad.as_str()), ("version-1", "version-7"));
+    assert_eq!(status.current.as_deref(), Some("version-4"));
+    assert_eq!((status.remaining, status.steps_left), (5, 3));
+
+    session.mark(true).unwrap();
+    let status = session.status();
+    assert_eq!(status.last_good, "version-4");
+    assert_eq!(status.current.as_deref(), Some("version-5"));
+    assert_eq!((status.remaining, status.steps_left), (2, 2));
+    assert_eq!(status.culprit, None);
+
+    // The last step leaves nothing between good and bad
+    session.mark(false).unwrap();
+    let status = session.status();
+    assert!(session.is_done());
+    assert_eq!(status.current, None);
+    assert_eq!(status.culprit.as_deref(), Some("version-5"));
+    assert_eq!((status.remaining, status.steps_left), (0, 0));
+    let verdicts: Vec<(&str, bool)> = status.verdicts.iter().map(|verdict| (verdict.version.as_str(), verdict.good)).collect();
+    assert_eq!(verdicts, [("version-4", true), ("version-5", false)]);
+
+    let error = session.mark(true).unwrap_err();
+    assert!(error.message().contains("already finished"), "{}", error);
+}
+
+#[test]
+fn neighbouring_versions_are_done_right_away() {
+    let session = session("version-2", "version-3");
+    assert!(session.is_done());
+    assert_eq!(session.status().culprit.as_deref(), Some("version-3"));
+    assert_eq!(session.status().steps_left, 0);
+}
+
+#[test]
+fn bad_input_is_rejected() {
+    let start = |good: &str, bad: &str| {
+        BisectSession::start(&history(), good, bad, "LIVE".to_string(), "WindowsPlayer".to_string()).err().unwrap()
+    };
+    assert_eq!(start("version-1", "version-f").kind(), "not_found");
+    assert_eq!(start("version-5", "version-2").kind(), "validation");
+    assert_eq!(start("version-4", "version-4").kind(), "validation");
+
+    let error = bisect::record_verdict("maybe").err().unwrap();
+    assert!(error.message().contains("Unknown verdict"), "{}", error);
+}
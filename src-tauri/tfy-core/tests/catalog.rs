@@ -0,0 +1,131 @@
+mod common;
+
+use common::{isolate, MockServer, Route};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use std::fs;
+use tfy_core::catalog::{self, Catalog};
+use tfy_core::{AppError, SharedClient};
+
+const TEST_SECRET: &str = "0707070707070707070707070707070707070707070707070707070707070707";
+
+fn client() -> SharedClient {
+    tfy_core::http::default_client()
+}
+
+fn test_key() -> VerifyingKey {
+    SigningKey::from_bytes(&[7; 32]).verifying_key()
+}
+
+// The bundled catalog with a new revision and one app renamed so it can be told apart
+fn remote_catalog(revision: u64, name: &str) -> String {
+    let mut catalog: Catalog = catalog::load_with(&test_key()).unwrap();
+    catalog.revision = revision;
+    catalog.apps[0].name = name.to_string();
+    serde_json::to_string_pretty(&catalog).unwrap()
+}
+
+fn clear_cache() {
+    let dir = dirs::data_dir().unwrap().join("tfy-tool");
+    let _ = fs::remove_file(dir.join("catalog.json"));
+    let _ = fs::remove_file(dir.join("catalog.json.sig"));
+}
+
+#[test]
+fn bundled_catalog_is_valid() {
+    let catalog = catalog::parse(include_str!("../catalog/apps.json")).unwrap();
+    assert!(catalog.find("vlc").is_ok());
+    // The Extra page downloads by id too
+    assert!(catalog.find("swift").unwrap().extra && catalog.find("solara").unwrap().extra);
+    assert!(matches!(catalog.find("nope"), Err(AppError::NotFound(_))));
+}
+
+#[test]
+fn apps_without_a_hash_have_to_say_why() {
+    let text = include_str!("../catalog/apps.json");
+    let unexplained = text.replacen(
+        r#""unpinned": "Vendor link to the latest release, the bytes change with every update","#,
+        "",
+        1,
+    );
+    let error = catalog::parse(&unexplained).unwrap_err();
+    assert!(error.message().contains("no sha256 for spotify"), "{}", error);
+}
+
+#[test]
+fn bundled_catalog_is_signed_with_the_release_key() {
+    let text = include_str!("../catalog/apps.json");
+    let signature = include_str!("../catalog/apps.json.sig");
+    assert!(catalog::verify(text, signature, &catalog::release_key()).is_ok());
+}
+
+#[tokio::test]
+async fn signed_catalog_is_accepted_and_cached() {
+    let _guard = isolate().await;
+    clear_cache();
+    let server = MockServer::start().await;
+    let text = remote_catalog(5, "Signed");
+    server.route("/apps.json", Route::Ok(text.clone().into_bytes()));
+    server.route("/apps.json.sig", Route::Ok(catalog::sign(&text, TEST_SECRET).unwrap().into_bytes()));
+
+    let refreshed = catalog::refresh_from(client().as_ref(), &server.url("/apps.json"), &test_key()).await.unwrap();
+
+    assert_eq!(refreshed.revision, 5);
+    assert_eq!(refreshed.apps[0].name, "Signed");
+    assert_eq!(catalog::load_with(&test_key()).unwrap().apps[0].name, "Signed");
+    // Not signed by the release key, so the app itself ignores the cached copy
+    assert_ne!(catalog::load().unwrap().apps[0].name, "Signed");
+}
+
+#[tokio::test]
+async fn tampered_catalog_falls_back_to_the_last_good_copy() {
+    let _guard = isolate().await;
+    clear_cache();
+    let server = MockServer::start().await;
+    let good = remote_catalog(5, "Good");
+    server.route("/apps.json", Route::Ok(good.clone().into_bytes()));
+    server.route("/apps.json.sig", Route::Ok(catalog::sign(&good, TEST_SECRET).unwrap().into_bytes()));
+    catalog::refresh_from(client().as_ref(), &server.url("/apps.json"), &test_key()).await.unwrap();
+
+    let tampered = good.replace("https://download.scdn.co/SpotifySetup.exe", "https://evil.example/SpotifySetup.exe");
+    server.route("/apps.json", Route::Ok(tampered.into_bytes()));
+
+    let error = catalog::refresh_from(client().as_ref(), &server.url("/apps.json"), &test_key()).await.unwrap_err();
+
+    assert_eq!(error.kind(), "validation", "{}", error);
+    let catalog = catalog::load_with(&test_key()).unwrap();
+    assert_eq!(catalog.apps[0].name, "Good");
+    assert_eq!(catalog.apps[0].url, "https://download.scdn.co/SpotifySetup.exe");
+}
+
+#[tokio::test]
+async fn unsigned_catalog_is_rejected() {
+    let _guard = isolate().await;
+    clear_cache();
+    let server = MockServer::start().await;
+    server.route("/apps.json", Route::Ok(remote_catalog(5, "Unsigned").into_bytes()));
+
+    let error = catalog::refresh_from(client().as_ref(), &server.url("/apps.json"), &test_key()).await.unwrap_err();
+
+    assert!(matches!(error, AppError::HttpStatus { status: 404, .. }), "{}", error);
+    assert_ne!(catalog::load_with(&test_key()).unwrap().apps[0].name, "Unsigned");
+}
+
+#[tokio::test]
+async fn older_signed_catalog_is_rejected() {
+    let _guard = isolate().await;
+    clear_cache();
+    let server = MockServer::start().await;
+    let newer = remote_catalog(6, "Newer");
+    server.route("/apps.json", Route::Ok(newer.clone().into_bytes()));
+    server.route("/apps.json.sig", Route::Ok(catalog::sign(&newer, TEST_SECRET).unwrap().into_bytes()));
+    catalog::refresh_from(client().as_ref(), &server.url("/apps.json"), &test_key()).await.unwrap();
+
+    let older = remote_catalog(5, "Older");
+    server.route("/apps.json", Route::Ok(older.clone().into_bytes()));
+    server.route("/apps.json.sig", Route::Ok(catalog::sign(&older, TEST_SECRET).unwrap().into_bytes()));
+
+    let error = catalog::refresh_from(client().as_ref(), &server.url("/apps.json"), &test_key()).await.unwrap_err();
+
+    assert_eq!(error.kind(), "validation", "{}", error);
+    assert_eq!(catalog::load_with(&test_key()).unwrap().apps[0].name, "Newer");
+}
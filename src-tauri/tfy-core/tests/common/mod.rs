@@ -0,0 +1,187 @@
+// Shared fixtures for the integration tests: a tiny in-process HTTP server and
+// helpers that build zip packages and rbxPkgManifest.txt files around them.
+#![allow(dead_code)]
+
+use md5::{Digest, Md5};
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::sync::{Arc, Mutex, OnceLock};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+#[derive(Clone)]
+pub enum Route {
+    Ok(Vec<u8>),
+    Status(u16),
+    // 302 to another path on the same server
+    Redirect(String),
+    // Advertises `claimed` bytes in Content-Length, sends `body` and hangs up
+    Truncated { body: Vec<u8>, claimed: usize },
+    // Like Truncated but keeps the connection open forever, a transfer in progress
+    Stalled { body: Vec<u8>, claimed: usize },
+    // 200 with extra response headers, e.g. Content-Disposition
+    Headers { headers: Vec<(String, String)>, body: Vec<u8> },
+    // Like Ok, but answers "Range: bytes=N-" with a 206 and the rest of the body,
+    // unless an If-Range header doesn't match the path's etag()
+    Ranged(Vec<u8>),
+}
+
+pub struct MockServer {
+    pub url: String,
+    routes: Arc<Mutex<HashMap<String, Route>>>,
+    hits: Arc<Mutex<Vec<String>>>,
+    // (path, Range header) of the requests that asked for a range
+    ranges: Arc<Mutex<Vec<(String, String)>>>,
+    // ETag headers sent with every response for a path
+    etags: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl MockServer {
+    pub async fn start() -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let routes: Arc<Mutex<HashMap<String, Route>>> = Arc::default();
+        let hits: Arc<Mutex<Vec<String>>> = Arc::default();
+        let ranges: Arc<Mutex<Vec<(String, String)>>> = Arc::default();
+        let etags: Arc<Mutex<HashMap<String, String>>> = Arc::default();
+
+        let (server_routes, server_hits, server_ranges, server_etags) =
+            (Arc::clone(&routes), Arc::clone(&hits), Arc::clone(&ranges), Arc::clone(&etags));
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let (routes, hits, ranges, etags) =
+                    (Arc::clone(&server_routes), Arc::clone(&server_hits), Arc::clone(&server_ranges), Arc::clone(&server_etags));
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut buffer = [0u8; 1024];
+                    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                        match stream.read(&mut buffer).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(read) => head.extend_from_slice(&buffer[..read]),
+                        }
+                    }
+
+                    let head = String::from_utf8_lossy(&head);
+                    let path = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+                    hits.lock().unwrap().push(path.clone());
+                    let header = |wanted: &str| head.lines()
+                        .find_map(|line| line.split_once(':').filter(|(name, _)| name.eq_ignore_ascii_case(wanted)))
+                        .map(|(_, value)| value.trim().to_string());
+                    let range = header("range");
+                    let if_range = header("if-range");
+                    if let Some(range) = &range {
+                        ranges.lock().unwrap().push((path.clone(), range.clone()));
+                    }
+                    let route = routes.lock().unwrap().get(&path).cloned().unwrap_or(Route::Status(404));
+                    let etag = etags.lock().unwrap().get(&path).cloned();
+
+                    let stall = matches!(route, Route::Stalled { .. });
+                    let (status, headers, body, claimed) = match route {
+                        Route::Ok(body) => (200, String::new(), body.clone(), body.len()),
+                        Route::Status(status) => (status, String::new(), Vec::new(), 0),
+                        Route::Redirect(location) => (302, format!("Location: {}\r\n", location), Vec::new(), 0),
+                        Route::Truncated { body, claimed } | Route::Stalled { body, claimed } => {
+                            (200, String::new(), body, claimed)
+                        }
+                        Route::Headers { headers, body } => {
+                            let headers = headers.iter().map(|(name, value)| format!("{}: {}\r\n", name, value)).collect();
+                            (200, headers, body.clone(), body.len())
+                        }
+                        Route::Ranged(body) => {
+                            let start = range.as_deref()
+                                .and_then(|range| range.strip_prefix("bytes="))
+                                .and_then(|range| range.strip_suffix('-'))
+                                .and_then(|start| start.parse::<usize>().ok())
+                                .filter(|start| *start < body.len())
+                                .filter(|_| if_range.is_none() || if_range == etag);
+                            match start {
+                                Some(start) => {
+                                    let headers = format!("Content-Range: bytes {}-{}/{}\r\n", start, body.len() - 1, body.len());
+                                    (206, headers, body[start..].to_vec(), body.len() - start)
+                                }
+                                None => (200, String::new(), body.clone(), body.len()),
+                            }
+                        }
+                    };
+
+                    let etag = etag.map(|etag| format!("ETag: {}\r\n", etag)).unwrap_or_default();
+                    let response = format!(
+                        "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n{}{}\r\n",
+                        status, claimed, headers, etag
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.write_all(&body).await;
+                    if stall {
+                        std::future::pending::<()>().await;
+                    }
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        MockServer { url, routes, hits, ranges, etags }
+    }
+
+    pub fn route(&self, path: &str, route: Route) {
+        self.routes.lock().unwrap().insert(path.to_string(), route);
+    }
+
+    // The version of the file at `path`, quoted like a real ETag
+    pub fn etag(&self, path: &str, etag: &str) {
+        self.etags.lock().unwrap().insert(path.to_string(), format!("\"{}\"", etag));
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.url, path)
+    }
+
+    pub fn hits(&self, path: &str) -> usize {
+        self.hits.lock().unwrap().iter().filter(|hit| *hit == path).count()
+    }
+
+    pub fn ranges(&self, path: &str) -> Vec<String> {
+        self.ranges.lock().unwrap().iter().filter(|(hit, _)| hit == path).map(|(_, range)| range.clone()).collect()
+    }
+}
+
+pub fn zip_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, contents) in files {
+        writer.start_file(*name, FileOptions::default()).unwrap();
+        writer.write_all(contents).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+pub fn md5_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Md5::digest(bytes))
+}
+
+// `packages` are (name, zip bytes, unpacked size)
+pub fn manifest_of(packages: &[(&str, &[u8], u64)]) -> String {
+    let mut manifest = String::from("v0\r\n");
+    for (name, bytes, unpacked) in packages {
+        manifest.push_str(&format!("{}\r\n{}\r\n{}\r\n{}\r\n", name, md5_hex(bytes), bytes.len(), unpacked));
+    }
+    manifest
+}
+
+// Points the app data, config and package cache directories at a throwaway
+// folder (through the XDG variables, so Linux only) and serialises the tests
+// that read and write the install registry.
+pub async fn isolate() -> tokio::sync::MutexGuard<'static, ()> {
+    static HOME: OnceLock<TempDir> = OnceLock::new();
+    static LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    HOME.get_or_init(|| {
+        let home = TempDir::new().unwrap();
+        std::env::set_var("XDG_DATA_HOME", home.path().join("data"));
+        std::env::set_var("XDG_CONFIG_HOME", home.path().join("config"));
+        std::env::set_var("XDG_CACHE_HOME", home.path().join("cache"));
+        home
+    });
+    LOCK.lock().await
+}
@@ -0,0 +1,35 @@
+use tfy_core::config::{validate_app_settings, AppSettingsConfig};
+
+#[test]
+fn app_settings_need_a_settings_root_and_content_folder() {
+    assert!(validate_app_settings("<Settings><ContentFolder>content</ContentFolder></Settings>").is_ok());
+    assert!(validate_app_settings(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Settings>\n  <ContentFolder> content </ContentFolder>\n</Settings>\n"
+    )
+    .is_ok());
+
+    let invalid = |xml: &str| validate_app_settings(xml).unwrap_err().message().to_string();
+    assert!(invalid("").contains("missing <Settings>"));
+    assert!(invalid("<?xml version=\"1.0\"").contains("missing <Settings>"));
+    assert!(invalid("<Settings><BaseUrl>x</BaseUrl></Settings>").contains("missing <ContentFolder>"));
+    assert!(invalid("<Settings><ContentFolder> </ContentFolder></Settings>").contains("is empty"));
+}
+
+#[test]
+fn templates_are_filled_in_and_checked() {
+    let settings = AppSettingsConfig {
+        content_folder: "assets".to_string(),
+        base_url: "https://example.com".to_string(),
+        player_template: Some("<Settings><ContentFolder>{content_folder}</ContentFolder><BaseUrl>{base_url}</BaseUrl></Settings>".to_string()),
+        studio_template: None,
+    };
+    assert_eq!(
+        settings.render("WindowsPlayer").unwrap().as_deref(),
+        Some("<Settings><ContentFolder>assets</ContentFolder><BaseUrl>https://example.com</BaseUrl></Settings>")
+    );
+    assert_eq!(settings.render("WindowsStudio64").unwrap(), None);
+
+    let broken = AppSettingsConfig { content_folder: String::new(), ..settings };
+    let error = broken.render("WindowsPlayer").unwrap_err();
+    assert!(error.message().contains("template is invalid"), "{}", error);
+}
@@ -0,0 +1,23 @@
+use tfy_core::deploy_history::parse_history;
+
+const HISTORY: &str = "\
+New Studio64 version-aaaa000000000000 at 1/23/2023 5:10:00 PM, file version: 0, 560, 0, 1
+New WindowsPlayer version-0123456789ABCDEF at 1/23/2023 5:18:53 PM, file version: 0, 560, 0, 5600
+New WindowsPlayer version-bbbb000000000000 at 1/24/2023 2:01:12 PM, file version: 0, 561, 0, 5610
+Revert WindowsPlayer version-bbbb000000000000 at 1/24/2023 3:00:00 PM
+New WindowsPlayer version-0123456789abcdef at 1/25/2023 9:00:00 AM, file version: 0, 560, 0, 5600
+New WindowsPlayer at 1/25/2023 9:30:00 AM
+";
+
+#[test]
+fn deploys_are_listed_in_order_once() {
+    let versions = parse_history(HISTORY, "WindowsPlayer").unwrap();
+    assert_eq!(versions, ["version-0123456789abcdef", "version-bbbb000000000000"]);
+}
+
+#[test]
+fn binary_types_map_to_their_history_names() {
+    assert_eq!(parse_history(HISTORY, "WindowsStudio64").unwrap(), ["version-aaaa000000000000"]);
+    assert!(parse_history(HISTORY, "MacPlayer").unwrap().is_empty());
+    assert_eq!(parse_history(HISTORY, "LinuxPlayer").unwrap_err().kind(), "validation");
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::cmp::Ordering;
+use std::fs;
+use tempfile::TempDir;
+use tfy_core::catalog::{self, Catalog};
+use tfy_core::detect::{self, compare_versions, DesktopDetector, Detector, InstalledApp};
+use tfy_core::AppError;
+
+fn bundled() -> Catalog {
+    catalog::parse(include_str!("../catalog/apps.json")).unwrap()
+}
+
+fn installed(name: &str, version: Option<&str>) -> InstalledApp {
+    InstalledApp {
+        name: name.to_string(),
+        version: version.map(str::to_string),
+        publisher: None,
+        location: None,
+    }
+}
+
+// Stands in for the registry
+struct Fixed(Vec<InstalledApp>);
+
+impl Detector for Fixed {
+    fn installed(&self) -> Result<Vec<InstalledApp>, AppError> {
+        Ok(self.0.clone())
+    }
+}
+
+#[test]
+fn versions_compare_numerically() {
+    assert_eq!(compare_versions("1.10", "1.9"), Some(Ordering::Greater));
+    assert_eq!(compare_versions("6.24", "6.24.0"), Some(Ordering::Equal));
+    assert_eq!(compare_versions("3.0.18", "3.0.20"), Some(Ordering::Less));
+    assert_eq!(compare_versions("v1.2 (64-bit)", "1.2"), Some(Ordering::Equal));
+    assert_eq!(compare_versions("latest", "1.0"), None);
+}
+
+#[test]
+fn catalog_apps_are_matched_to_installed_ones() {
+    let detector = Fixed(vec![
+        installed("Steamworks Common Redistributables", None),
+        installed("VLC media player", Some("3.0.18")),
+        installed("WinRAR 6.24 (64-bit)", Some("6.24.0")),
+        installed("Microsoft Visual Studio Code (User)", Some("1.94.2")),
+        installed("Discord", Some("1.0.9150")),
+        installed("discord", Some("1.0.9163")),
+    ]);
+
+    let statuses = detect::detect(&bundled().apps, &detector).unwrap();
+    let status = |id: &str| statuses.iter().find(|status| status.id == id).unwrap();
+    let version = |id: &str| status(id).installed.as_ref().and_then(|app| app.version.clone());
+
+    assert_eq!(statuses.len(), bundled().apps.len());
+    assert!(status("steam").installed.is_none(), "Steamworks is not Steam");
+    assert_eq!(version("vlc").as_deref(), Some("3.0.18"));
+    assert!(status("vlc").update_available);
+    assert_eq!(version("winrar").as_deref(), Some("6.24.0"));
+    assert!(!status("winrar").update_available);
+    // Found through installed_name, but the catalog has no version to compare
+    assert_eq!(version("vscode").as_deref(), Some("1.94.2"));
+    assert!(!status("vscode").update_available);
+    assert_eq!(version("discord").as_deref(), Some("1.0.9163"), "the newest copy is reported");
+    assert!(status("spotify").installed.is_none());
+}
+
+#[test]
+fn desktop_files_and_known_paths_are_found() {
+    let home = TempDir::new().unwrap();
+    let applications = home.path().join("applications");
+    fs::create_dir_all(&applications).unwrap();
+    fs::write(
+        applications.join("steam.desktop"),
+        "[Desktop Entry]\nName=Steam\nName[de]=Steam DE\nExec=steam\n\n[Desktop Action Library]\nName=Library\n",
+    )
+    .unwrap();
+    fs::write(
+        applications.join("stremio.desktop"),
+        "[Desktop Entry]\nName = Stremio\nX-AppImage-Version=4.4.168\n",
+    )
+    .unwrap();
+    fs::write(applications.join("removed.desktop"), "[Desktop Entry]\nName=Spotify\nHidden=true\n").unwrap();
+    fs::write(applications.join("notes.txt"), "Name=Brave\n").unwrap();
+    let vlc = home.path().join("vlc");
+    fs::write(&vlc, "").unwrap();
+
+    let detector = DesktopDetector { dirs: vec![applications.clone(), home.path().join("missing")], known: Vec::new() }
+        .with_path("VLC", &vlc)
+        .with_path("WinRAR", home.path().join("winrar"));
+    let found = detector.installed().unwrap();
+
+    let names: Vec<&str> = found.iter().map(|app| app.name.as_str()).collect();
+    assert_eq!(names, ["Steam", "Stremio", "VLC"]);
+    assert_eq!(found[0].location.as_deref(), Some(applications.join("steam.desktop").as_path()));
+    assert_eq!(found[1].version.as_deref(), Some("4.4.168"));
+    assert_eq!(found[2].location.as_deref(), Some(vlc.as_path()));
+}
+
+#[tokio::test]
+async fn linux_builds_of_catalog_apps_are_found_by_desktop_file_id() {
+    let _guard = common::isolate().await;
+    let applications = dirs::data_dir().unwrap().join("applications");
+    fs::create_dir_all(&applications).unwrap();
+    fs::write(applications.join("code.desktop"), "[Desktop Entry]\nName=Visual Studio Code\n").unwrap();
+    fs::write(applications.join("com.valvesoftware.Steam.desktop"), "[Desktop Entry]\nName=Steam\n").unwrap();
+
+    let statuses = detect::detect(&bundled().apps, &DesktopDetector::system()).unwrap();
+    let located = |id: &str| {
+        statuses.iter().find(|status| status.id == id).unwrap().installed.as_ref().and_then(|app| app.location.clone())
+    };
+
+    // Name= alone would not match the catalog's "Microsoft Visual Studio Code"
+    assert_eq!(located("vscode"), Some(applications.join("code.desktop")));
+    assert!(located("steam").is_some());
+    let _ = fs::remove_dir_all(&applications);
+}
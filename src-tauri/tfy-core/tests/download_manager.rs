@@ -0,0 +1,362 @@
+mod common;
+
+use common::{isolate, MockServer, Route};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tempfile::TempDir;
+use tfy_core::apps::DownloadRequest;
+use tfy_core::downloads::{self, DownloadManager, DownloadRecord, DownloadStatus};
+use tfy_core::AppError;
+
+const SHA256_OF_MZ_SETUP: &str = "574ce2739035aaff515080c231b4fb9ed9103174d63e201caec23d3d9a657dfc";
+
+// Every test starts from an empty history, the data directory is shared
+fn manager(limit: usize) -> DownloadManager {
+    let history = dirs::data_dir().unwrap().join("tfy-tool").join("downloads.json");
+    let _ = fs::remove_file(history);
+    DownloadManager::new(tfy_core::http::default_client(), limit)
+}
+
+fn request(server: &MockServer, path: &str, dir: &Path) -> DownloadRequest {
+    let filename = path.rsplit('/').next().unwrap();
+    DownloadRequest {
+        name: filename.to_string(),
+        url: server.url(path),
+        dir: dir.to_path_buf(),
+        filename: Some(filename.to_string()),
+        sha256: None,
+        claimed_publisher: None,
+    }
+}
+
+async fn wait_for(manager: &DownloadManager, id: u64, status: DownloadStatus) {
+    for _ in 0..500 {
+        if manager.get(id).unwrap().status == status {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("download {} never became {:?}, it is {:?}", id, status, manager.get(id).unwrap().status);
+}
+
+#[tokio::test]
+async fn queue_runs_one_at_a_time_and_cancels() {
+    let _guard = isolate().await;
+    let manager = manager(1);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/slow.exe", Route::Stalled { body: b"MZ".to_vec(), claimed: 4096 });
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+
+    let slow = manager.enqueue(request(&server, "/files/slow.exe", dir.path())).unwrap();
+    let next = manager.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    wait_for(&manager, slow.id, DownloadStatus::Running).await;
+    assert_eq!(manager.get(next.id).unwrap().status, DownloadStatus::Queued);
+
+    manager.cancel(slow.id).unwrap();
+
+    let error = manager.wait(slow.id).await.unwrap_err();
+    assert!(matches!(error, AppError::Cancelled(_)), "{}", error);
+    let done = manager.wait(next.id).await.unwrap();
+    assert_eq!(done.file.unwrap().sha256, SHA256_OF_MZ_SETUP);
+    assert!(!slow.partial_path().exists(), "partial file left behind");
+    assert!(manager.get(slow.id).unwrap().finished_at.is_some());
+}
+
+// Pauses a download once the stalled server has sent its first `sent` bytes
+async fn pause_after(manager: &DownloadManager, server: &MockServer, dir: &Path, sent: &[u8]) -> DownloadRecord {
+    server.route("/files/setup.exe", Route::Stalled { body: sent.to_vec(), claimed: 8 });
+    server.etag("/files/setup.exe", "v1");
+    let download = manager.enqueue(request(server, "/files/setup.exe", dir)).unwrap();
+    let partial = download.partial_path();
+    for _ in 0..500 {
+        if fs::metadata(&partial).is_ok_and(|metadata| metadata.len() == sent.len() as u64) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    manager.pause(download.id).unwrap();
+    wait_for(manager, download.id, DownloadStatus::Paused).await;
+    assert_eq!(fs::read(&partial).unwrap(), sent, "the partial file is kept");
+    download
+}
+
+#[tokio::test]
+async fn paused_downloads_resume_where_they_stopped() {
+    let _guard = isolate().await;
+    let manager = manager(2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    let download = pause_after(&manager, &server, dir.path(), b"MZ se").await;
+
+    server.route("/files/setup.exe", Route::Ranged(b"MZ setup".to_vec()));
+    manager.resume(download.id).unwrap();
+
+    let done = manager.wait(download.id).await.unwrap();
+    assert_eq!(server.ranges("/files/setup.exe"), ["bytes=5-"]);
+    assert_eq!(done.attempts, 2);
+    assert_eq!(fs::read(&done.path).unwrap(), b"MZ setup");
+    // The hash covers the bytes from before the pause too
+    assert_eq!(done.file.unwrap().sha256, SHA256_OF_MZ_SETUP);
+    assert!(!download.partial_path().exists());
+}
+
+#[tokio::test]
+async fn downloads_of_the_same_file_keep_their_own_partial_file() {
+    let _guard = isolate().await;
+    let manager = manager(2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    let paused = pause_after(&manager, &server, dir.path(), b"MZ se").await;
+
+    server.route("/files/setup.exe", Route::Ok(b"MZ other".to_vec()));
+    let other = manager.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    assert_ne!(other.partial_path(), paused.partial_path());
+    manager.wait(other.id).await.unwrap();
+    assert_eq!(fs::read(paused.partial_path()).unwrap(), b"MZ se", "the paused download's partial file was touched");
+
+    server.route("/files/setup.exe", Route::Ranged(b"MZ setup".to_vec()));
+    manager.resume(paused.id).unwrap();
+    let done = manager.wait(paused.id).await.unwrap();
+    assert_eq!(done.file.unwrap().sha256, SHA256_OF_MZ_SETUP);
+}
+
+#[tokio::test]
+async fn resumes_start_over_when_the_server_ignores_the_range() {
+    let _guard = isolate().await;
+    let manager = manager(2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    let download = pause_after(&manager, &server, dir.path(), b"MZ se").await;
+
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+    manager.resume(download.id).unwrap();
+
+    let done = manager.wait(download.id).await.unwrap();
+    assert_eq!(server.ranges("/files/setup.exe"), ["bytes=5-"]);
+    assert_eq!(fs::read(&done.path).unwrap(), b"MZ setup");
+    assert_eq!(done.file.unwrap().sha256, SHA256_OF_MZ_SETUP);
+}
+
+#[tokio::test]
+async fn resumes_start_over_when_the_file_changed() {
+    let _guard = isolate().await;
+    let manager = manager(2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    let download = pause_after(&manager, &server, dir.path(), b"MZ ol").await;
+
+    server.route("/files/setup.exe", Route::Ranged(b"MZ setup".to_vec()));
+    server.etag("/files/setup.exe", "v2");
+    manager.resume(download.id).unwrap();
+
+    let done = manager.wait(download.id).await.unwrap();
+    assert_eq!(server.ranges("/files/setup.exe"), ["bytes=5-"]);
+    assert_eq!(fs::read(&done.path).unwrap(), b"MZ setup");
+    assert_eq!(done.file.unwrap().sha256, SHA256_OF_MZ_SETUP);
+}
+
+#[tokio::test]
+async fn cancelling_a_paused_download_deletes_its_partial_file() {
+    let _guard = isolate().await;
+    let manager = manager(2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    let download = pause_after(&manager, &server, dir.path(), b"MZ").await;
+
+    manager.cancel(download.id).unwrap();
+    assert!(!download.partial_path().exists(), "partial file left behind");
+
+    server.route("/files/setup.exe", Route::Ranged(b"MZ setup".to_vec()));
+    manager.retry(download.id).unwrap();
+    assert_eq!(fs::read(manager.wait(download.id).await.unwrap().path).unwrap(), b"MZ setup");
+    assert!(server.ranges("/files/setup.exe").is_empty(), "a retry starts over");
+}
+
+#[tokio::test]
+async fn failed_downloads_can_be_retried() {
+    let _guard = isolate().await;
+    let manager = manager(2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+
+    let download = manager.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    let error = manager.wait(download.id).await.unwrap_err();
+    assert!(matches!(error, AppError::HttpStatus { status: 404, .. }), "{}", error);
+    assert!(manager.resume(download.id).is_err(), "only paused downloads resume");
+
+    let failed = manager.get(download.id).unwrap();
+    assert_eq!(failed.status, DownloadStatus::Failed);
+    assert!(failed.error.unwrap().contains("404"));
+
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+    manager.retry(download.id).unwrap();
+
+    let done = manager.wait(download.id).await.unwrap();
+    assert_eq!(done.attempts, 2);
+    assert_eq!(done.error, None);
+}
+
+#[tokio::test]
+async fn history_survives_a_restart() {
+    let _guard = isolate().await;
+    let manager = manager(2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+    server.route("/files/slow.exe", Route::Stalled { body: b"MZ".to_vec(), claimed: 4096 });
+
+    let done = manager.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    manager.wait(done.id).await.unwrap();
+
+    // The first manager's runtime goes away mid-transfer, as in a crash
+    let interrupted = {
+        let (manager, request) = (manager.clone(), request(&server, "/files/slow.exe", dir.path()));
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            runtime.block_on(async {
+                let interrupted = manager.enqueue(request).unwrap();
+                wait_for(&manager, interrupted.id, DownloadStatus::Running).await;
+                interrupted
+            })
+        }).join().unwrap()
+    };
+
+    // A second manager reads what the first one left on disk
+    let restarted = DownloadManager::new(tfy_core::http::default_client(), 2);
+
+    let done = restarted.get(done.id).unwrap();
+    assert_eq!(done.status, DownloadStatus::Completed);
+    assert_eq!(done.request.url, server.url("/files/setup.exe"));
+    assert_eq!(done.path, dir.path().join("setup.exe"));
+    let file = done.file.unwrap();
+    assert_eq!((file.size, file.sha256.as_str()), (8, SHA256_OF_MZ_SETUP));
+    assert!(done.queued_at > 0 && done.finished_at >= done.started_at);
+    assert_eq!(restarted.get(interrupted.id).unwrap().status, DownloadStatus::Running, "new() starts nothing");
+
+    assert_eq!(restarted.restore().unwrap(), 1);
+    wait_for(&restarted, interrupted.id, DownloadStatus::Running).await;
+    assert_eq!(restarted.get(interrupted.id).unwrap().attempts, 2);
+
+    // New ids carry on after the restored ones
+    let later = restarted.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    assert!(later.id > interrupted.id);
+    assert_eq!(downloads::load().unwrap().downloads.len(), 3);
+}
+
+#[tokio::test]
+async fn restore_leaves_downloads_running_elsewhere_alone() {
+    let _guard = isolate().await;
+    let cli = manager(2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/slow.exe", Route::Stalled { body: b"MZ".to_vec(), claimed: 4096 });
+
+    let running = cli.enqueue(request(&server, "/files/slow.exe", dir.path())).unwrap();
+    wait_for(&cli, running.id, DownloadStatus::Running).await;
+
+    let gui = DownloadManager::new(tfy_core::http::default_client(), 2);
+    assert_eq!(gui.restore().unwrap(), 0);
+    let download = gui.get(running.id).unwrap();
+    assert_eq!((download.status, download.attempts), (DownloadStatus::Running, 1));
+
+    cli.cancel(running.id).unwrap();
+    assert!(matches!(cli.wait(running.id).await.unwrap_err(), AppError::Cancelled(_)));
+}
+
+#[tokio::test]
+async fn managers_in_two_processes_share_the_history() {
+    let _guard = isolate().await;
+    let gui = manager(2);
+    let cli = DownloadManager::new(tfy_core::http::default_client(), 2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+
+    // Neither overwrites the other's records or hands out an id twice
+    let first = gui.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    let second = cli.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    let third = gui.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    assert_eq!([first.id, second.id, third.id], [1, 2, 3]);
+
+    gui.wait(first.id).await.unwrap();
+    cli.wait(second.id).await.unwrap();
+    gui.wait(third.id).await.unwrap();
+
+    let statuses: Vec<(u64, DownloadStatus)> = downloads::load().unwrap().downloads.iter()
+        .map(|download| (download.id, download.status))
+        .collect();
+    assert_eq!(statuses, [(1, DownloadStatus::Completed), (2, DownloadStatus::Completed), (3, DownloadStatus::Completed)]);
+    assert_eq!(gui.get(second.id).unwrap().status, DownloadStatus::Completed);
+    assert_eq!(gui.list().len(), 3);
+}
+
+#[tokio::test]
+async fn managers_only_start_what_they_queued() {
+    let _guard = isolate().await;
+    let gui = manager(1);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/slow.exe", Route::Stalled { body: b"MZ".to_vec(), claimed: 4096 });
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+
+    let slow = gui.enqueue(request(&server, "/files/slow.exe", dir.path())).unwrap();
+    let waiting = gui.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+
+    // A CLI run with room for more only runs its own download
+    let cli = DownloadManager::new(tfy_core::http::default_client(), 2);
+    let mine = cli.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    cli.wait(mine.id).await.unwrap();
+    assert_eq!(cli.get(waiting.id).unwrap().status, DownloadStatus::Queued);
+    assert_eq!(server.hits("/files/setup.exe"), 1);
+
+    gui.cancel(slow.id).unwrap();
+    gui.wait(waiting.id).await.unwrap();
+}
+
+#[tokio::test]
+async fn clear_history_keeps_unfinished_downloads() {
+    let _guard = isolate().await;
+    let manager = manager(1);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+    server.route("/files/slow.exe", Route::Stalled { body: b"MZ".to_vec(), claimed: 4096 });
+
+    let done = manager.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    manager.wait(done.id).await.unwrap();
+    let running = manager.enqueue(request(&server, "/files/slow.exe", dir.path())).unwrap();
+    let paused = manager.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    manager.pause(paused.id).unwrap();
+
+    assert_eq!(manager.clear_history().unwrap(), 1);
+
+    let ids: Vec<u64> = manager.list().iter().map(|download| download.id).collect();
+    assert_eq!(ids, [running.id, paused.id]);
+    assert!(dir.path().join("setup.exe").exists(), "clearing the history deletes no files");
+}
+
+#[tokio::test]
+async fn only_unchanged_completed_downloads_run() {
+    let _guard = isolate().await;
+    let manager = manager(2);
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+
+    let download = manager.enqueue(request(&server, "/files/setup.exe", dir.path())).unwrap();
+    let download = manager.wait(download.id).await.unwrap();
+    assert!(download.summary.unwrap().contains(SHA256_OF_MZ_SETUP));
+
+    // Swapped after the user saw the summary
+    fs::write(&download.path, b"MZ swapped").unwrap();
+    let error = manager.run(download.id).unwrap_err();
+    assert_eq!(error.kind(), "validation", "{}", error);
+    assert!(error.message().contains("changed since it was downloaded"), "{}", error);
+
+    fs::remove_file(&download.path).unwrap();
+    assert_eq!(manager.run(download.id).unwrap_err().kind(), "not_found");
+    assert_eq!(manager.run(u64::MAX).unwrap_err().kind(), "not_found");
+}
@@ -0,0 +1,380 @@
+mod common;
+
+use common::{isolate, manifest_of, zip_of, MockServer, Route};
+use std::fs;
+use tempfile::TempDir;
+use tfy_core::apps::{DownloadRequest, DownloadedFile};
+use tfy_core::downloads::DownloadManager;
+use tfy_core::catalog::CatalogApp;
+use tfy_core::installer::{self, PlayerRequest};
+use tfy_core::{apps, packages, registry, versions, AppError, NoProgress, SharedClient};
+
+const SHA256_OF_MZ_SETUP: &str = "574ce2739035aaff515080c231b4fb9ed9103174d63e201caec23d3d9a657dfc";
+
+fn client() -> SharedClient {
+    tfy_core::http::default_client()
+}
+
+// Catalog downloads go through the queue and its history like they do in the app
+async fn download_app(app: &CatalogApp, dir: &TempDir) -> Result<DownloadedFile, AppError> {
+    let _guard = isolate().await;
+    let downloads = DownloadManager::new(client(), 1);
+    let download = downloads.enqueue(DownloadRequest::for_app(app, dir.path()))?;
+    downloads.wait(download.id).await.map(|download| download.file.expect("completed downloads have a file"))
+}
+
+fn catalog_app(url: String, filename: &str, sha256: Option<&str>) -> CatalogApp {
+    CatalogApp {
+        id: filename.to_string(),
+        name: filename.to_string(),
+        icon: String::new(),
+        color: None,
+        url,
+        filename: filename.to_string(),
+        sha256: sha256.map(str::to_string),
+        unpinned: None,
+        claimed_publisher: None,
+        installer: None,
+        silent_args: Vec::new(),
+        installed_name: None,
+        version: None,
+        extra: false,
+    }
+}
+
+// Serves a two package WindowsPlayer version under /<version>-<package> and
+// returns the request for it. Package contents include `tag` so that every
+// test gets its own checksums and never hits another test's package cache.
+fn serve_version(server: &MockServer, version: &str, tag: &str, install_root: &TempDir) -> PlayerRequest {
+    let app = zip_of(&[("RobloxPlayerBeta.exe", format!("player {}", tag).as_bytes())]);
+    let shaders = zip_of(&[("shaders_d3d11.pack", format!("shaders {}", tag).as_bytes())]);
+    let manifest = manifest_of(&[("RobloxApp.zip", &app, 64), ("shaders.zip", &shaders, 64)]);
+
+    server.route(&format!("/{}-rbxPkgManifest.txt", version), Route::Ok(manifest.into_bytes()));
+    server.route(&format!("/{}-RobloxApp.zip", version), Route::Ok(app));
+    server.route(&format!("/{}-shaders.zip", version), Route::Ok(shaders));
+
+    let mut request = PlayerRequest::new(version, "LIVE", "WindowsPlayer");
+    request.cdn_url = Some(server.url.clone());
+    request.install_root = Some(install_root.path().to_path_buf());
+    request
+}
+
+#[tokio::test]
+async fn download_player_installs_from_the_cdn() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let install_root = TempDir::new().unwrap();
+    let request = serve_version(&server, "version-aaaa0001", "happy", &install_root);
+
+    let record = installer::download_player(client().as_ref(), &NoProgress, &request).await.unwrap();
+
+    let install = install_root.path().join("version-aaaa0001");
+    assert_eq!(record.path, install);
+    assert_eq!(fs::read(install.join("RobloxPlayerBeta.exe")).unwrap(), b"player happy");
+    assert_eq!(fs::read(install.join("shaders/shaders_d3d11.pack")).unwrap(), b"shaders happy");
+    assert!(install.join("tfy-index.json").is_file());
+    assert!(install.join("rbxPkgManifest.txt").is_file());
+    assert!(installer::verify_install(&record).unwrap().is_empty());
+
+    let registry = registry::load().unwrap();
+    assert!(registry.find("version-aaaa0001", Some("WindowsPlayer")).is_ok());
+    assert!(registry.staging.is_empty());
+}
+
+#[tokio::test]
+async fn download_player_reports_a_forbidden_manifest() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let install_root = TempDir::new().unwrap();
+    let request = serve_version(&server, "version-aaaa0002", "forbidden", &install_root);
+    server.route("/version-aaaa0002-rbxPkgManifest.txt", Route::Status(403));
+
+    let error = installer::download_player(client().as_ref(), &NoProgress, &request).await.unwrap_err();
+
+    assert!(matches!(error, AppError::HttpStatus { status: 403, .. }), "{}", error);
+    assert_eq!(server.hits("/version-aaaa0002-RobloxApp.zip"), 0);
+    assert!(!install_root.path().join("version-aaaa0002").exists());
+}
+
+#[tokio::test]
+async fn download_player_rolls_back_a_truncated_package() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let install_root = TempDir::new().unwrap();
+    let request = serve_version(&server, "version-aaaa0003", "truncated", &install_root);
+
+    let shaders = zip_of(&[("shaders_d3d11.pack", b"shaders truncated")]);
+    server.route(
+        "/version-aaaa0003-shaders.zip",
+        Route::Truncated { body: shaders[..shaders.len() / 2].to_vec(), claimed: shaders.len() },
+    );
+
+    let error = installer::download_player(client().as_ref(), &NoProgress, &request).await.unwrap_err();
+
+    assert_eq!(error.kind(), "network", "{}", error);
+    assert!(!install_root.path().join("version-aaaa0003").exists());
+    assert_eq!(fs::read_dir(install_root.path()).unwrap().count(), 0, "staging directory left behind");
+    assert!(registry::load().unwrap().staging.is_empty());
+}
+
+#[tokio::test]
+async fn download_player_rejects_a_bad_checksum() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let install_root = TempDir::new().unwrap();
+    let request = serve_version(&server, "version-aaaa0004", "checksum", &install_root);
+
+    // Same size as the real package, different bytes
+    let app = zip_of(&[("RobloxPlayerBeta.exe", b"player checksum")]);
+    let mut tampered = app.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 0xff;
+    server.route("/version-aaaa0004-RobloxApp.zip", Route::Ok(tampered));
+
+    let error = installer::download_player(client().as_ref(), &NoProgress, &request).await.unwrap_err();
+
+    assert_eq!(error.kind(), "validation", "{}", error);
+    assert!(error.message().contains("Checksum mismatch"), "{}", error);
+    assert!(!install_root.path().join("version-aaaa0004").exists());
+}
+
+#[test]
+fn packages_cannot_write_outside_the_install() {
+    let root = TempDir::new().unwrap();
+    let install_dir = root.path().join("version-1");
+
+    let zip = zip_of(&[("fonts\\a.ttf", b"font")]);
+    let files = packages::extract_package("content-fonts.zip", zip, &install_dir, "content/").unwrap();
+    assert!(files.contains_key("content/fonts/a.ttf"));
+
+    for entry in ["..\\..\\evil.txt", "../evil.txt", "/evil.txt"] {
+        let zip = zip_of(&[(entry, b"evil")]);
+        let error = packages::extract_package("content-fonts.zip", zip, &install_dir, "").unwrap_err();
+        assert!(error.message().contains("unsafe path"), "{}: {}", entry, error);
+    }
+    assert!(!root.path().join("evil.txt").exists());
+}
+
+#[test]
+fn extracting_over_a_hardlinked_file_leaves_the_other_install_alone() {
+    let root = TempDir::new().unwrap();
+    let (first, second) = (root.path().join("version-1"), root.path().join("version-2"));
+    for install_dir in [&first, &second] {
+        packages::extract_package("shared.zip", zip_of(&[("a.dll", b"old")]), install_dir, "").unwrap();
+    }
+    // What dedupe leaves behind
+    fs::remove_file(second.join("a.dll")).unwrap();
+    fs::hard_link(first.join("a.dll"), second.join("a.dll")).unwrap();
+
+    packages::extract_package("shared.zip", zip_of(&[("a.dll", b"new")]), &first, "").unwrap();
+
+    assert_eq!(fs::read(first.join("a.dll")).unwrap(), b"new");
+    assert_eq!(fs::read(second.join("a.dll")).unwrap(), b"old");
+}
+
+#[tokio::test]
+async fn download_player_follows_redirects() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let install_root = TempDir::new().unwrap();
+    let request = serve_version(&server, "version-aaaa0005", "redirect", &install_root);
+
+    let app = zip_of(&[("RobloxPlayerBeta.exe", b"player redirect")]);
+    server.route("/mirror/RobloxApp.zip", Route::Ok(app));
+    server.route("/version-aaaa0005-RobloxApp.zip", Route::Redirect("/mirror/RobloxApp.zip".to_string()));
+
+    let record = installer::download_player(client().as_ref(), &NoProgress, &request).await.unwrap();
+
+    assert_eq!(server.hits("/mirror/RobloxApp.zip"), 1);
+    assert_eq!(fs::read(record.path.join("RobloxPlayerBeta.exe")).unwrap(), b"player redirect");
+}
+
+#[tokio::test]
+async fn saved_versions_are_parsed_and_sorted() {
+    let server = MockServer::start().await;
+    let versions_json = br#"{ "version-bbbb": "Second", "aaaa": "First", "version-cccc": 3 }"#;
+    server.route("/versions", Route::Ok(versions_json.to_vec()));
+
+    let saved = versions::fetch_saved_versions(client().as_ref(), &server.url("/versions")).await.unwrap();
+
+    let hashes: Vec<&str> = saved.iter().map(|version| version.hash.as_str()).collect();
+    assert_eq!(hashes, ["aaaa", "bbbb", "cccc"]);
+    assert_eq!(saved[0].description, "First");
+    assert_eq!(saved[2].description, "");
+}
+
+#[tokio::test]
+async fn saved_versions_report_http_errors() {
+    let server = MockServer::start().await;
+    server.route("/versions", Route::Status(403));
+
+    let error = versions::fetch_saved_versions(client().as_ref(), &server.url("/versions")).await.unwrap_err();
+
+    assert!(matches!(error, AppError::HttpStatus { status: 403, .. }), "{}", error);
+}
+
+#[tokio::test]
+async fn download_app_saves_the_file() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+    server.route("/latest", Route::Redirect("/files/setup.exe".to_string()));
+    let app = catalog_app(server.url("/latest"), "setup.exe", None);
+
+    let file = download_app(&app, &dir).await.unwrap();
+
+    assert_eq!(file.path, dir.path().join("setup.exe"));
+    assert_eq!(fs::read(&file.path).unwrap(), b"MZ setup");
+    // Unpinned, but the hash is still reported
+    assert_eq!(file.sha256, SHA256_OF_MZ_SETUP);
+    assert!(!file.verified);
+}
+
+#[tokio::test]
+async fn download_app_keeps_files_already_in_the_folder() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+    let app = catalog_app(server.url("/files/setup.exe"), "setup.exe", None);
+    fs::write(dir.path().join("setup.exe"), b"kept").unwrap();
+
+    let first = download_app(&app, &dir).await.unwrap();
+    let second = download_app(&app, &dir).await.unwrap();
+
+    assert_eq!(first.path, dir.path().join("setup (1).exe"));
+    assert_eq!(second.path, dir.path().join("setup (2).exe"));
+    assert_eq!(fs::read(dir.path().join("setup.exe")).unwrap(), b"kept");
+    assert_eq!(fs::read(&second.path).unwrap(), b"MZ setup");
+}
+
+#[tokio::test]
+async fn download_app_accepts_a_matching_hash() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/setup.exe", Route::Ok(b"MZ setup".to_vec()));
+    let app = catalog_app(server.url("/files/setup.exe"), "setup.exe", Some(&SHA256_OF_MZ_SETUP.to_uppercase()));
+
+    let file = download_app(&app, &dir).await.unwrap();
+
+    assert!(file.verified);
+    assert_eq!(file.size, 8);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1, "partial file left behind");
+}
+
+#[tokio::test]
+async fn download_app_does_not_save_error_pages() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/gone.exe", Route::Status(404));
+
+    let app = catalog_app(server.url("/files/gone.exe"), "gone.exe", None);
+
+    let error = download_app(&app, &dir).await.unwrap_err();
+
+    assert!(matches!(error, AppError::HttpStatus { status: 404, .. }), "{}", error);
+    assert!(!dir.path().join("gone.exe").exists());
+}
+
+#[tokio::test]
+async fn download_app_does_not_save_truncated_files() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/big.exe", Route::Truncated { body: b"MZ half".to_vec(), claimed: 4096 });
+
+    let app = catalog_app(server.url("/files/big.exe"), "big.exe", None);
+
+    let error = download_app(&app, &dir).await.unwrap_err();
+
+    assert_eq!(error.kind(), "network", "{}", error);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0, "truncated file left behind");
+}
+
+#[tokio::test]
+async fn download_app_checks_the_catalog_hash() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/setup.exe", Route::Ok(b"MZ tampered".to_vec()));
+    let app = catalog_app(server.url("/files/setup.exe"), "setup.exe", Some(SHA256_OF_MZ_SETUP));
+
+    let error = download_app(&app, &dir).await.unwrap_err();
+
+    assert_eq!(error.kind(), "validation", "{}", error);
+    assert!(error.message().contains("Checksum mismatch"), "{}", error);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0, "rejected file left behind");
+}
+
+#[tokio::test]
+async fn download_app_rejects_a_different_claimed_publisher() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/tool.exe", Route::Ok(include_bytes!("fixtures/signed-x64.exe").to_vec()));
+    let mut app = catalog_app(server.url("/files/tool.exe"), "tool.exe", None);
+
+    app.claimed_publisher = Some("TFY Test Publisher".to_string());
+    let file = download_app(&app, &dir).await.unwrap();
+    assert_eq!(file.executable.unwrap().product_name.as_deref(), Some("TFY Fixture"));
+
+    app.claimed_publisher = Some("Someone Else".to_string());
+    let error = download_app(&app, &dir).await.unwrap_err();
+    assert_eq!(error.kind(), "validation", "{}", error);
+    assert!(error.message().contains("claims to be signed by TFY Test Publisher"), "{}", error);
+    assert!(!dir.path().join("tool (1).exe").exists());
+}
+
+fn unnamed_request(server: &MockServer, path: &str, name: &str, dir: &TempDir) -> DownloadRequest {
+    DownloadRequest {
+        name: name.to_string(),
+        url: server.url(path),
+        dir: dir.path().to_path_buf(),
+        filename: None,
+        sha256: None,
+        claimed_publisher: None,
+    }
+}
+
+#[tokio::test]
+async fn download_names_the_file_after_the_redirect() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    let msi = [&[0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1][..], b"installer"].concat();
+    server.route("/api/download?platform=win", Route::Redirect("/distro/DiscordSetup.msi".to_string()));
+    server.route("/distro/DiscordSetup.msi", Route::Ok(msi));
+    let request = unnamed_request(&server, "/api/download?platform=win", "Discord", &dir);
+
+    let file = apps::download(client().as_ref(), &request).await.unwrap();
+
+    assert_eq!(file.path, dir.path().join("DiscordSetup.msi"));
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1, "partial file left behind");
+}
+
+#[tokio::test]
+async fn download_takes_a_sanitised_content_disposition_name() {
+    let server = MockServer::start().await;
+    let root = TempDir::new().unwrap();
+    let dir = TempDir::new_in(root.path()).unwrap();
+    let zip = zip_of(&[("readme.txt", b"hello")]);
+    let headers = vec![("Content-Disposition".to_string(), r#"attachment; filename="../Tools.exe""#.to_string())];
+    server.route("/api/download", Route::Headers { headers, body: zip.clone() });
+    let request = unnamed_request(&server, "/api/download", "Tools", &dir);
+
+    let file = apps::download(client().as_ref(), &request).await.unwrap();
+
+    // Not ../, and a zip is not saved as something that would be run
+    assert_eq!(file.path, dir.path().join("Tools.zip"));
+    assert_eq!(fs::read(&file.path).unwrap(), zip);
+    assert_eq!(fs::read_dir(root.path()).unwrap().count(), 1);
+}
+
+#[tokio::test]
+async fn download_gives_extensionless_executables_an_exe() {
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/api/download", Route::Ok(b"MZ setup".to_vec()));
+    let request = unnamed_request(&server, "/api/download", "CON", &dir);
+
+    let file = apps::download(client().as_ref(), &request).await.unwrap();
+
+    assert_eq!(file.path, dir.path().join("_CON.exe"));
+    assert_eq!(file.sha256, SHA256_OF_MZ_SETUP);
+}
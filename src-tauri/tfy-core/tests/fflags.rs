@@ -0,0 +1,30 @@
+use serde_json::json;
+use tfy_core::fflags::{self, FlagSet};
+
+fn flags(value: serde_json::Value) -> FlagSet {
+    value.as_object().unwrap().clone()
+}
+
+#[test]
+fn flags_are_plain_name_value_pairs() {
+    let parsed = fflags::parse_flags(r#"{ "FFlagDebugGraphicsPreferD3D11": true, "DFIntTaskSchedulerTargetFps": 144, "FStringName": "x" }"#).unwrap();
+    assert_eq!(parsed.len(), 3);
+    assert_eq!(parsed["DFIntTaskSchedulerTargetFps"], json!(144));
+
+    let invalid = |text: &str| fflags::parse_flags(text).unwrap_err().message().to_string();
+    assert!(invalid("{ nope").contains("Invalid flag JSON"));
+    assert!(invalid("[1, 2]").contains("must be an object"));
+    assert!(invalid(r#"{ "FFlagNested": { "a": 1 } }"#).contains("FFlagNested must be a string, number or boolean"));
+    assert!(invalid(r#"{ "FFlagList": [] }"#).contains("FFlagList"));
+    assert!(invalid(r#"{ " ": true }"#).contains("cannot be empty"));
+}
+
+#[test]
+fn merging_adds_or_replaces() {
+    let mut current = flags(json!({ "A": 1, "B": 2 }));
+    fflags::merge_flags(&mut current, flags(json!({ "B": 3, "C": 4 })), false);
+    assert_eq!(serde_json::Value::Object(current.clone()), json!({ "A": 1, "B": 3, "C": 4 }));
+
+    fflags::merge_flags(&mut current, flags(json!({ "D": 5 })), true);
+    assert_eq!(serde_json::Value::Object(current), json!({ "D": 5 }));
+}
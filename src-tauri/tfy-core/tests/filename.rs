@@ -0,0 +1,91 @@
+use tfy_core::filename::{self, FileKind};
+
+const MSI_MAGIC: [u8; 8] = [0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1];
+
+#[test]
+fn content_disposition_names_are_parsed() {
+    let name = |header| filename::from_content_disposition(header);
+
+    assert_eq!(name(r#"attachment; filename="DiscordSetup.exe""#).as_deref(), Some("DiscordSetup.exe"));
+    assert_eq!(name("attachment; filename=setup.msi").as_deref(), Some("setup.msi"));
+    assert_eq!(name(r#"attachment; FILENAME="a \"quoted\"; name.zip""#).as_deref(), Some(r#"a "quoted"; name.zip"#));
+    // filename* wins wherever it appears
+    assert_eq!(
+        name(r#"attachment; filename*=UTF-8''na%C3%AFve%20setup.exe; filename="naive.exe""#).as_deref(),
+        Some("naïve setup.exe")
+    );
+    assert_eq!(name("attachment; filename*=iso-8859-1'en'%E9t%E9.exe").as_deref(), Some("été.exe"));
+    // An unknown charset falls back to the plain parameter
+    assert_eq!(name("attachment; filename*=koi8-r''x.exe; filename=y.exe").as_deref(), Some("y.exe"));
+    assert_eq!(name("attachment; size; filename=z.exe").as_deref(), Some("z.exe"));
+    assert_eq!(name("inline"), None);
+    assert_eq!(name(r#"attachment; filename="""#), None);
+}
+
+#[test]
+fn url_names_are_the_last_segment() {
+    assert_eq!(
+        filename::from_url("https://dl.discordapp.net/distro/app/stable/win/x86/DiscordSetup.exe?x=1#top").as_deref(),
+        Some("DiscordSetup.exe")
+    );
+    assert_eq!(filename::from_url("https://example.com/files/My%20Setup.msi").as_deref(), Some("My Setup.msi"));
+    assert_eq!(filename::from_url("https://example.com/").as_deref(), None);
+    assert_eq!(filename::from_url("not a url"), None);
+}
+
+#[test]
+fn names_are_sanitised() {
+    let sanitize = |name| filename::sanitize(name);
+
+    assert_eq!(sanitize("../../evil.exe").as_deref(), Some("evil.exe"));
+    assert_eq!(sanitize(r"C:\Windows\System32\evil.dll").as_deref(), Some("evil.dll"));
+    assert_eq!(sanitize("a<b>:c\"|?*\u{7}.exe").as_deref(), Some("abc.exe"));
+    assert_eq!(sanitize(" setup.exe. . ").as_deref(), Some("setup.exe"));
+    assert_eq!(sanitize(".hidden").as_deref(), Some("hidden"));
+    assert_eq!(sanitize("CON.exe").as_deref(), Some("_CON.exe"));
+    assert_eq!(sanitize("com1").as_deref(), Some("_com1"));
+    assert_eq!(sanitize("lpt9.txt").as_deref(), Some("_lpt9.txt"));
+    assert_eq!(sanitize("console.exe").as_deref(), Some("console.exe"));
+    assert_eq!(sanitize(".."), None);
+    assert_eq!(sanitize("dir/"), None);
+
+    let long = format!("{}.exe", "a".repeat(400));
+    let sanitised = sanitize(&long).unwrap();
+    assert_eq!(sanitised.len(), 154);
+    assert!(sanitised.ends_with(".exe"));
+}
+
+#[test]
+fn extensions_follow_the_contents() {
+    let pe = filename::sniff(b"MZ\x90\x00\x03\x00\x00\x00");
+    let msi = filename::sniff(&MSI_MAGIC);
+    let zip = filename::sniff(b"PK\x03\x04\x14\x00\x00\x00");
+    assert_eq!((pe, msi, zip), (Some(FileKind::Pe), Some(FileKind::Msi), Some(FileKind::Zip)));
+    assert_eq!(filename::sniff(b"<html>"), None);
+    assert_eq!(filename::sniff(b""), None);
+
+    assert_eq!(filename::with_kind("setup.exe", zip), "setup.zip");
+    assert_eq!(filename::with_kind("Setup.EXE", pe), "Setup.EXE");
+    assert_eq!(filename::with_kind("Discord", msi), "Discord.msi");
+    assert_eq!(filename::with_kind("tool-1.0.9", pe), "tool-1.0.9.exe");
+    assert_eq!(filename::with_kind("App.msix", zip), "App.msix");
+    assert_eq!(filename::with_kind("notes.txt", None), "notes.txt");
+}
+
+#[test]
+fn copies_are_numbered_before_the_extension() {
+    assert_eq!(filename::numbered("setup.exe", 1), "setup (1).exe");
+    assert_eq!(filename::numbered("tool-1.0.9", 2), "tool-1.0.9 (2)");
+}
+
+#[test]
+fn resolve_prefers_what_the_server_says() {
+    let url = "https://cdn.example.com/builds/Setup-2.1.exe";
+
+    assert_eq!(filename::resolve(Some("attachment; filename=\"Real.msi\""), url, "App"), "Real.msi");
+    assert_eq!(filename::resolve(Some("attachment"), url, "App"), "Setup-2.1.exe");
+    // .../api/download says nothing about the file
+    assert_eq!(filename::resolve(None, "https://example.com/api/download?platform=win", "Discord"), "Discord");
+    assert_eq!(filename::resolve(None, "https://example.com/", "../NUL"), "_NUL");
+    assert_eq!(filename::resolve(None, "https://example.com/", "/"), "download");
+}
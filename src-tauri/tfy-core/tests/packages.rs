@@ -0,0 +1,27 @@
+mod common;
+
+use common::{manifest_of, md5_hex};
+use tfy_core::packages::parse_manifest;
+
+#[test]
+fn manifests_list_only_the_zip_packages() {
+    let text = manifest_of(&[("content-fonts.zip", b"fonts", 10), ("RobloxPlayerBeta.exe", b"exe", 3)]);
+    let packages = parse_manifest(&text).unwrap();
+
+    assert_eq!(packages.len(), 1);
+    assert_eq!(packages[0].name, "content-fonts.zip");
+    assert_eq!(packages[0].checksum, md5_hex(b"fonts"));
+    assert_eq!((packages[0].packed_size, packages[0].unpacked_size), (5, 10));
+}
+
+#[test]
+fn broken_manifests_are_rejected() {
+    let checksum = md5_hex(b"fonts");
+    let invalid = |text: String| parse_manifest(&text).err().unwrap().message().to_string();
+
+    assert!(invalid(String::new()).contains("empty"));
+    assert!(invalid("v1\r\n".to_string()).contains("Unsupported manifest version: v1"));
+    assert!(invalid(format!("v0\r\na.zip\r\n{}\r\n5\r\n", checksum)).contains("Truncated manifest entry for a.zip"));
+    assert!(invalid("v0\r\na.zip\r\nnot-a-checksum\r\n5\r\n10\r\n".to_string()).contains("Invalid checksum"));
+    assert!(invalid(format!("v0\r\na.zip\r\n{}\r\nfive\r\n10\r\n", checksum)).contains("Invalid size 'five'"));
+}
@@ -0,0 +1,80 @@
+// The fixtures are minimal hand-built images, not programs: signed-x64.exe is a
+// PE32+ GUI image with a version resource and a PKCS#7 blob signed by
+// "CN=TFY Test Publisher" (issued by a separate test root, both certificates
+// are in the blob). plain-x86.exe is a bare PE32 console image.
+use tfy_core::pe::{self, PeInfo};
+
+const SIGNED: &[u8] = include_bytes!("fixtures/signed-x64.exe");
+const PLAIN: &[u8] = include_bytes!("fixtures/plain-x86.exe");
+
+#[test]
+fn signed_executable_reports_headers_version_and_signer() {
+    let info = pe::inspect(SIGNED).unwrap();
+
+    assert_eq!(info, PeInfo {
+        machine: "x64".to_string(),
+        subsystem: "gui".to_string(),
+        company_name: Some("TFY Test".to_string()),
+        product_name: Some("TFY Fixture".to_string()),
+        file_version: Some("1.2.3.4".to_string()),
+        signed: true,
+        signer: Some("CN=TFY Test Publisher, O=TFY Test, L=Test City, S=Test State, C=US".to_string()),
+        claimed_publisher: Some("TFY Test Publisher".to_string()),
+    });
+    assert_eq!(info.describe(), "TFY Fixture 1.2.3.4 (x64 gui), claims to be signed by TFY Test Publisher (not verified)");
+}
+
+#[test]
+fn plain_executable_has_no_version_or_signature() {
+    let info = pe::inspect(PLAIN).unwrap();
+
+    assert_eq!(info.machine, "x86");
+    assert_eq!(info.subsystem, "console");
+    assert_eq!((&info.company_name, &info.product_name, &info.file_version), (&None, &None, &None));
+    assert!(!info.signed);
+    assert_eq!(info.signer, None);
+    assert_eq!(info.describe(), "no version info (x86 console), not signed");
+}
+
+#[test]
+fn damaged_signature_keeps_the_rest() {
+    // Cut into the certificate table: the directory now points past the end of the file
+    let info = pe::inspect(&SIGNED[..SIGNED.len() - 100]).unwrap();
+
+    assert!(!info.signed);
+    assert_eq!(info.product_name.as_deref(), Some("TFY Fixture"));
+}
+
+#[test]
+fn non_executables_are_rejected() {
+    for data in [&b""[..], b"PK\x03\x04 not an exe", b"MZ", &SIGNED[..0x60]] {
+        let error = pe::inspect(data).unwrap_err();
+        assert_eq!(error.kind(), "validation", "{}", error);
+    }
+}
+
+#[test]
+fn garbage_after_the_headers_does_not_panic() {
+    // Every truncation point and a few flipped bytes through the resource and certificate data
+    for length in 0..SIGNED.len() {
+        let _ = pe::inspect(&SIGNED[..length]);
+    }
+    for offset in (0x200..SIGNED.len()).step_by(7) {
+        let mut data = SIGNED.to_vec();
+        data[offset] ^= 0xff;
+        let _ = pe::inspect(&data);
+    }
+}
+
+#[test]
+fn files_are_read_like_the_same_bytes_in_memory() {
+    let dir = tempfile::TempDir::new().unwrap();
+    for (name, data) in [("signed.exe", SIGNED), ("plain.exe", PLAIN), ("cut.exe", &SIGNED[..SIGNED.len() - 100])] {
+        let path = dir.path().join(name);
+        std::fs::write(&path, data).unwrap();
+        assert_eq!(pe::inspect_file(&path).unwrap(), pe::inspect(data).unwrap(), "{}", name);
+    }
+    let path = dir.path().join("setup.zip");
+    std::fs::write(&path, b"PK\x03\x04 not an exe").unwrap();
+    assert_eq!(pe::inspect_file(&path).unwrap_err().kind(), "validation");
+}
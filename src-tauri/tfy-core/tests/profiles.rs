@@ -0,0 +1,167 @@
+mod common;
+
+use common::{isolate, MockServer, Route};
+use std::fs;
+use std::sync::Mutex;
+use tempfile::TempDir;
+use tfy_core::catalog::{self, Catalog, CatalogApp};
+use tfy_core::downloads::DownloadManager;
+use tfy_core::profiles::{self, InstallProfile};
+use tfy_core::setup::{InstallOutcome, InstallerKind};
+use tfy_core::NoProgress;
+
+fn bundled() -> Catalog {
+    catalog::parse(include_str!("../catalog/apps.json")).unwrap()
+}
+
+fn gaming_rig() -> InstallProfile {
+    let apps = ["steam", "discord", "vlc", "brave"].map(str::to_string).to_vec();
+    InstallProfile::new("Gaming rig", apps)
+}
+
+fn clear_profiles() {
+    let _ = fs::remove_dir_all(dirs::data_dir().unwrap().join("tfy-tool").join("profiles"));
+}
+
+fn portable_app(server: &MockServer, id: &str, installer: Option<InstallerKind>) -> CatalogApp {
+    CatalogApp {
+        id: id.to_string(),
+        name: id.to_uppercase(),
+        icon: String::new(),
+        color: None,
+        url: server.url(&format!("/files/{}.exe", id)),
+        filename: format!("{}.exe", id),
+        sha256: None,
+        unpinned: Some("test".to_string()),
+        claimed_publisher: None,
+        installer,
+        silent_args: Vec::new(),
+        installed_name: None,
+        version: None,
+        extra: false,
+    }
+}
+
+#[tokio::test]
+async fn profiles_survive_an_export_and_import() {
+    let _guard = isolate().await;
+    clear_profiles();
+    let catalog = bundled();
+    let elsewhere = TempDir::new().unwrap();
+    let exported = elsewhere.path().join("gaming-rig.json");
+
+    profiles::save(&gaming_rig(), &catalog).unwrap();
+    profiles::save(&InstallProfile::new("Office", vec!["vscode".to_string()]), &catalog).unwrap();
+    let names: Vec<String> = profiles::list().unwrap().into_iter().map(|profile| profile.name).collect();
+    assert_eq!(names, ["Gaming rig", "Office"]);
+
+    profiles::export("GAMING RIG", &exported).unwrap();
+    profiles::delete("Gaming rig").unwrap();
+    assert_eq!(profiles::load("Gaming rig").unwrap_err().kind(), "not_found");
+
+    let imported = profiles::import(&exported, &catalog).unwrap();
+    assert_eq!(imported, gaming_rig());
+    assert_eq!(profiles::load("gaming rig").unwrap(), gaming_rig());
+}
+
+#[tokio::test]
+async fn broken_profiles_are_rejected() {
+    let _guard = isolate().await;
+    clear_profiles();
+    let catalog = bundled();
+    let invalid = |profile: InstallProfile| profiles::save(&profile, &catalog).unwrap_err();
+
+    assert_eq!(invalid(InstallProfile::new("Mystery", vec!["nope".to_string()])).kind(), "not_found");
+    let twice = invalid(InstallProfile::new("Twice", vec!["vlc".to_string(), "vlc".to_string()]));
+    assert!(twice.message().contains("lists vlc twice"), "{}", twice);
+    assert!(invalid(InstallProfile::new("Empty", Vec::new())).message().contains("has no apps"));
+    assert_eq!(invalid(InstallProfile::new("..", vec!["vlc".to_string()])).kind(), "validation");
+    // Installers that would wait for someone to click through them
+    for id in ["faceit-ac", "tuxler-vpn"] {
+        let manual = invalid(InstallProfile::new("Manual", vec!["steam".to_string(), id.to_string()]));
+        assert!(manual.message().contains("has no unattended install"), "{}", manual);
+    }
+
+    let newer = TempDir::new().unwrap();
+    let path = newer.path().join("newer.json");
+    fs::write(&path, r#"{ "format": 2, "name": "Newer", "apps": ["vlc"] }"#).unwrap();
+    let error = profiles::import(&path, &catalog).unwrap_err();
+    assert!(error.message().contains("Unsupported install profile format"), "{}", error);
+    assert!(profiles::list().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn run_profile_installs_in_order_and_reports_every_app() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/first.exe", Route::Ok(b"MZ first".to_vec()));
+    server.route("/files/manual.exe", Route::Ok(b"MZ manual".to_vec()));
+    server.route("/files/last.exe", Route::Ok(b"MZ last".to_vec()));
+    let catalog = Catalog {
+        format: 1,
+        revision: 1,
+        apps: vec![
+            portable_app(&server, "first", Some(InstallerKind::Portable)),
+            portable_app(&server, "missing", Some(InstallerKind::Portable)),
+            portable_app(&server, "manual", None),
+            portable_app(&server, "last", Some(InstallerKind::Portable)),
+        ],
+    };
+    let ids = ["first", "missing", "manual", "last", "gone"].map(str::to_string).to_vec();
+    let profile = InstallProfile::new("Lab", ids);
+
+    let lines = Mutex::new(Vec::new());
+    let progress = |line: &str| lines.lock().unwrap().push(line.to_string());
+    let questions = Mutex::new(Vec::new());
+    let confirm = |title: &str, message: &str| {
+        questions.lock().unwrap().push(format!("{} {}", title, message));
+        true
+    };
+    let downloads = DownloadManager::new(tfy_core::http::default_client(), 2);
+    let report = profiles::run_profile(&downloads, &catalog, &profile, dir.path(), &progress, &confirm).await.unwrap();
+
+    // Asked once, before anything was downloaded
+    let questions = questions.into_inner().unwrap();
+    assert_eq!(questions.len(), 1, "{:?}", questions);
+    assert!(questions[0].starts_with("Run Lab? Downloads and installs FIRST, MISSING, MANUAL, LAST, gone"), "{}", questions[0]);
+
+    let outcomes: Vec<(&str, InstallOutcome)> = report.steps.iter().map(|step| (step.app_id.as_str(), step.outcome)).collect();
+    assert_eq!(
+        outcomes,
+        [
+            ("first", InstallOutcome::Installed),
+            ("missing", InstallOutcome::Failed),
+            ("manual", InstallOutcome::Failed),
+            ("last", InstallOutcome::Installed),
+            ("gone", InstallOutcome::Failed),
+        ]
+    );
+    assert!(report.steps[1].message.contains("404"), "{}", report.steps[1].message);
+    assert!(report.steps[2].message.contains("no unattended install"), "{}", report.steps[2].message);
+    assert_eq!(report.summary, "Lab: 2 of 5 apps installed, 3 failed.");
+    assert!(!report.reboot_required());
+
+    // The downloads went through the queue one after the other
+    let queued: Vec<String> = downloads.list().into_iter().map(|download| download.request.name).collect();
+    assert_eq!(queued, ["FIRST", "MISSING", "MANUAL", "LAST"]);
+    assert_eq!(lines.lock().unwrap().first().map(String::as_str), Some("Installing FIRST (1/5)"));
+}
+
+#[tokio::test]
+async fn declined_profiles_install_nothing() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/first.exe", Route::Ok(b"MZ first".to_vec()));
+    let catalog = Catalog { format: 1, revision: 1, apps: vec![portable_app(&server, "first", Some(InstallerKind::Portable))] };
+    let profile = InstallProfile::new("Lab", vec!["first".to_string()]);
+
+    let downloads = DownloadManager::new(tfy_core::http::default_client(), 2);
+    let declined = |_: &str, _: &str| false;
+    let error = profiles::run_profile(&downloads, &catalog, &profile, dir.path(), &NoProgress, &declined).await.unwrap_err();
+
+    assert_eq!(error.kind(), "cancelled", "{}", error);
+    assert_eq!(server.hits("/files/first.exe"), 0);
+    assert!(downloads.list().is_empty());
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use common::{isolate, md5_hex, zip_of, MockServer, Route};
+use std::fs;
+use tempfile::TempDir;
+use tfy_core::packages::{PackageEntry, PackageSource};
+use tfy_core::repair::{self, PackedFile};
+
+// Bytes that don't compress, so the zip really is bigger than its last 64 KiB
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+fn entry(name: &str, bytes: &[u8]) -> PackageEntry {
+    PackageEntry {
+        name: name.to_string(),
+        checksum: md5_hex(bytes),
+        packed_size: bytes.len() as u64,
+        unpacked_size: 0,
+    }
+}
+
+#[test]
+fn zip_directories_are_read_from_the_end_of_the_zip() {
+    let zip = zip_of(&[("a.txt", b"hello"), ("sub/b.bin", b"xyz")]);
+
+    let offset = repair::directory_offset(&zip).unwrap() as usize;
+    let files = repair::parse_directory("test.zip", &zip[offset..]).unwrap();
+    assert_eq!(
+        files,
+        [
+            PackedFile { name: "a.txt".to_string(), size: 5, crc32: crc32fast::hash(b"hello") },
+            PackedFile { name: "sub/b.bin".to_string(), size: 3, crc32: crc32fast::hash(b"xyz") },
+        ]
+    );
+
+    assert_eq!(repair::directory_offset(b"not a zip at all, no directory here"), None);
+    assert!(repair::parse_directory("test.zip", b"garbage").unwrap().is_empty());
+    let truncated = &zip[offset..offset + 50];
+    assert_eq!(repair::parse_directory("test.zip", truncated).unwrap_err().kind(), "archive");
+}
+
+#[tokio::test]
+async fn packages_are_checked_from_a_range_request() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let client = tfy_core::http::default_client();
+    let source = PackageSource::Cdn { client: client.as_ref(), base_url: server.url.clone(), blob_dir: "/".to_string() };
+
+    let big = noise(100_000);
+    let zip = zip_of(&[("content/big.bin", &big), ("small.txt", b"small")]);
+    let package = entry("big.zip", &zip);
+    server.route("/version-r1-big.zip", Route::Ranged(zip.clone()));
+
+    let files = repair::list_package(&source, "version-r1", &package).await.unwrap();
+    let names: Vec<&str> = files.iter().map(|file| file.name.as_str()).collect();
+    assert_eq!(names, ["content/big.bin", "small.txt"]);
+    // Only the last 64 KiB went over the wire
+    assert_eq!(server.ranges("/version-r1-big.zip"), [format!("bytes={}-", zip.len() - 22 - 0xFFFF)]);
+
+    // Paths come back relative to the install folder, under the package's extract root
+    let install = TempDir::new().unwrap();
+    fs::create_dir_all(install.path().join("extra")).unwrap();
+    fs::write(install.path().join("extra/small.txt"), b"small").unwrap();
+    assert_eq!(repair::find_damaged(&files, install.path(), "extra/"), ["extra/content/big.bin"]);
+    fs::write(install.path().join("extra/small.txt"), b"SMALL").unwrap();
+    assert_eq!(repair::find_damaged(&files, install.path(), "extra/").len(), 2);
+}
+
+#[tokio::test]
+async fn servers_without_ranges_send_the_whole_package() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let client = tfy_core::http::default_client();
+    let source = PackageSource::Cdn { client: client.as_ref(), base_url: server.url.clone(), blob_dir: "/".to_string() };
+
+    let zip = zip_of(&[("whole.bin", &noise(70_000))]);
+    let package = entry("whole.zip", &zip);
+    server.route("/version-r2-whole.zip", Route::Ok(zip.clone()));
+
+    let files = repair::list_package(&source, "version-r2", &package).await.unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].size, 70_000);
+
+    // That download was verified and cached, the next check needs no request
+    repair::list_package(&source, "version-r2", &package).await.unwrap();
+    assert_eq!(server.hits("/version-r2-whole.zip"), 1);
+
+    let mut tampered = package;
+    tampered.checksum = "0".repeat(32);
+    let error = repair::list_package(&source, "version-r2", &tampered).await.unwrap_err();
+    assert!(error.message().contains("Checksum mismatch"), "{}", error);
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use common::{isolate, MockServer, Route};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tempfile::TempDir;
+use tfy_core::catalog::CatalogApp;
+use tfy_core::config::{self, RunMode};
+use tfy_core::downloads::DownloadManager;
+use tfy_core::setup::{self, InstallOutcome, InstallerKind};
+
+fn app(installer: Option<InstallerKind>, silent_args: &[&str]) -> CatalogApp {
+    CatalogApp {
+        id: "tool".to_string(),
+        name: "Tool".to_string(),
+        icon: String::new(),
+        color: None,
+        url: String::new(),
+        filename: "tool.exe".to_string(),
+        sha256: None,
+        unpinned: Some("test".to_string()),
+        claimed_publisher: None,
+        installer,
+        silent_args: silent_args.iter().map(|arg| arg.to_string()).collect(),
+        installed_name: None,
+        version: None,
+        extra: false,
+    }
+}
+
+#[test]
+fn exit_codes_map_to_outcomes() {
+    use InstallOutcome::*;
+    let outcome = |kind, code| setup::outcome(kind, code).0;
+
+    assert_eq!(outcome(Some(InstallerKind::Nsis), Some(0)), Installed);
+    assert_eq!(outcome(None, Some(3010)), RebootRequired);
+    assert_eq!(outcome(Some(InstallerKind::Msi), Some(1641)), RebootRequired);
+    assert_eq!(outcome(Some(InstallerKind::Msi), Some(1618)), Failed);
+    assert_eq!(outcome(Some(InstallerKind::Squirrel), Some(-1)), Failed);
+    assert_eq!(outcome(Some(InstallerKind::Inno), None), Failed);
+    // Inno's 8 is a pending restart that stopped the install, not a finished one
+    assert_eq!(setup::outcome(Some(InstallerKind::Inno), Some(8)), (Failed, "needs Windows to restart before it can install"));
+    assert_eq!(setup::outcome(Some(InstallerKind::Nsis), Some(1)).1, "was cancelled");
+}
+
+#[test]
+fn silent_commands_follow_the_installer_kind() {
+    let exe = Path::new("C:/Downloads/tool.exe");
+    let msi = Path::new("C:/Downloads/My Tool.msi");
+    let command = |app: CatalogApp, path: &Path| setup::silent_command(&app, path).unwrap();
+
+    assert_eq!(
+        command(app(Some(InstallerKind::Inno), &[]), exe),
+        (exe.to_path_buf(), vec!["/VERYSILENT".into(), "/SUPPRESSMSGBOXES".into(), "/NORESTART".into(), "/SP-".into()])
+    );
+    // The catalog's flags replace the defaults
+    assert_eq!(command(app(Some(InstallerKind::Nsis), &["/L=1033", "/S"]), exe).1, ["/L=1033", "/S"]);
+    // An undeclared .msi still goes through msiexec
+    assert_eq!(
+        command(app(None, &[]), msi),
+        (PathBuf::from("msiexec"), vec!["/i".into(), "C:/Downloads/My Tool.msi".into(), "/qn".into(), "/norestart".into()])
+    );
+    assert_eq!(command(app(None, &["/silent"]), exe).1, ["/silent"]);
+
+    let error = setup::silent_command(&app(None, &[]), exe).unwrap_err();
+    assert_eq!(error.kind(), "validation", "{}", error);
+    assert!(error.message().contains("no unattended install"), "{}", error);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn installers_are_waited_for_and_their_exit_code_read() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new().unwrap();
+    let installer = dir.path().join("tool.exe");
+    fs::write(&installer, "#!/bin/sh\ntest \"$1\" = /S && exit \"$2\"\nexit 99\n").unwrap();
+    fs::set_permissions(&installer, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let installed = setup::install(&app(Some(InstallerKind::Nsis), &["/S", "0"]), &installer).await.unwrap();
+    assert_eq!((installed.outcome, installed.exit_code), (InstallOutcome::Installed, Some(0)));
+    assert_eq!(installed.message, "Tool installed");
+
+    // Exit codes above 255 don't survive a Unix process, 3010 is covered above
+    let cancelled = setup::install(&app(Some(InstallerKind::Inno), &["/S", "5"]), &installer).await.unwrap();
+    assert_eq!(cancelled.message, "Tool was cancelled (exit code 5)");
+
+    let failed = setup::install(&app(Some(InstallerKind::Nsis), &["/S", "2"]), &installer).await.unwrap();
+    assert_eq!(failed.outcome, InstallOutcome::Failed);
+    assert_eq!(failed.message, "Tool was aborted by the installer (exit code 2)");
+
+    let missing = setup::install(&app(None, &["/S"]), &dir.path().join("gone.exe")).await.unwrap_err();
+    assert_eq!(missing.kind(), "io", "{}", missing);
+}
+
+#[tokio::test]
+async fn portable_apps_are_downloaded_and_copied_into_place() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/tool.exe", Route::Ok(b"MZ tool".to_vec()));
+    let mut portable = app(Some(InstallerKind::Portable), &[]);
+    portable.url = server.url("/files/tool.exe");
+
+    let downloads = DownloadManager::new(tfy_core::http::default_client(), 2);
+    let summary = Mutex::new(String::new());
+    let confirm = |title: &str, message: &str| {
+        *summary.lock().unwrap() = format!("{}\n{}", title, message);
+        true
+    };
+    let result = setup::install_app(&downloads, &portable, dir.path(), &confirm).await.unwrap();
+
+    assert_eq!(result.outcome, InstallOutcome::Installed);
+    assert_eq!(result.path, setup::portable_dir().unwrap().join("tool").join("tool.exe"));
+    assert_eq!(fs::read(&result.path).unwrap(), b"MZ tool");
+    assert!(dir.path().join("tool.exe").is_file(), "the download stays where it was saved");
+
+    // The file's details were shown before anything was copied
+    let summary = summary.into_inner().unwrap();
+    assert!(summary.starts_with("Install Tool?"), "{}", summary);
+    assert!(summary.contains("SHA-256 (not pinned"), "{}", summary);
+}
+
+#[tokio::test]
+async fn declined_installs_leave_the_download_alone() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/tool.exe", Route::Ok(b"MZ tool".to_vec()));
+    let mut portable = app(Some(InstallerKind::Portable), &[]);
+    portable.url = server.url("/files/tool.exe");
+
+    let downloads = DownloadManager::new(tfy_core::http::default_client(), 2);
+    let declined = |_: &str, _: &str| false;
+    let error = setup::install_app(&downloads, &portable, dir.path(), &declined).await.unwrap_err();
+
+    assert_eq!(error.kind(), "cancelled", "{}", error);
+    assert!(dir.path().join("tool.exe").is_file());
+    assert!(!setup::portable_dir().unwrap().join("tool").exists(), "nothing was installed");
+}
+
+#[tokio::test]
+async fn run_mode_auto_installs_without_asking() {
+    let _guard = isolate().await;
+    let server = MockServer::start().await;
+    let dir = TempDir::new().unwrap();
+    server.route("/files/tool.exe", Route::Ok(b"MZ tool".to_vec()));
+    let mut portable = app(Some(InstallerKind::Portable), &[]);
+    portable.url = server.url("/files/tool.exe");
+    let mut config = config::load().unwrap();
+    config.downloads.run_mode = RunMode::Auto;
+    config.save().unwrap();
+
+    let downloads = DownloadManager::new(tfy_core::http::default_client(), 2);
+    let never = |_: &str, _: &str| panic!("asked although run_mode is auto");
+    let result = setup::install_app(&downloads, &portable, dir.path(), &never).await.unwrap();
+    assert_eq!(result.outcome, InstallOutcome::Installed);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::isolate;
+use std::fs;
+use tempfile::TempDir;
+use tfy_core::{registry, staging};
+
+#[test]
+fn staging_carries_over_user_files() {
+    let root = TempDir::new().unwrap();
+    let output_dir = root.path().join("version-1");
+    fs::create_dir_all(output_dir.join("ClientSettings")).unwrap();
+    fs::write(output_dir.join("ClientSettings").join("ClientAppSettings.json"), "{}").unwrap();
+    fs::write(output_dir.join("AppSettings.xml"), "<Settings/>").unwrap();
+    fs::write(output_dir.join("RobloxPlayerBeta.exe"), "old").unwrap();
+
+    let staging_dir = staging::staging_dir(&output_dir);
+    assert_eq!(staging_dir, root.path().join(".version-1.staging"));
+    fs::create_dir_all(&staging_dir).unwrap();
+    fs::write(staging_dir.join("leftover"), "from a failed install").unwrap();
+    staging::prepare(&staging_dir, &output_dir).unwrap();
+
+    assert!(!staging_dir.join("leftover").exists());
+    assert!(staging_dir.join("ClientSettings").join("ClientAppSettings.json").exists());
+    assert!(staging_dir.join("AppSettings.xml").exists());
+    assert!(!staging_dir.join("RobloxPlayerBeta.exe").exists());
+}
+
+#[test]
+fn commit_moves_a_new_install_into_place() {
+    let root = TempDir::new().unwrap();
+    let output_dir = root.path().join("version-1");
+    let staging_dir = staging::staging_dir(&output_dir);
+    staging::prepare(&staging_dir, &output_dir).unwrap();
+    fs::write(staging_dir.join("RobloxPlayerBeta.exe"), "new").unwrap();
+
+    staging::commit(&staging_dir, &output_dir).unwrap();
+
+    assert_eq!(fs::read_to_string(output_dir.join("RobloxPlayerBeta.exe")).unwrap(), "new");
+    assert!(!staging_dir.exists());
+}
+
+#[test]
+fn commit_replaces_an_existing_install() {
+    let root = TempDir::new().unwrap();
+    let output_dir = root.path().join("version-1");
+    fs::create_dir_all(&output_dir).unwrap();
+    fs::write(output_dir.join("RobloxPlayerBeta.exe"), "old").unwrap();
+    fs::write(output_dir.join("removed.dll"), "old").unwrap();
+    let staging_dir = staging::staging_dir(&output_dir);
+    staging::prepare(&staging_dir, &output_dir).unwrap();
+    fs::write(staging_dir.join("RobloxPlayerBeta.exe"), "new").unwrap();
+
+    staging::commit(&staging_dir, &output_dir).unwrap();
+
+    assert_eq!(fs::read_to_string(output_dir.join("RobloxPlayerBeta.exe")).unwrap(), "new");
+    assert!(!output_dir.join("removed.dll").exists());
+    let names: Vec<_> = fs::read_dir(root.path()).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(names, ["version-1"]);
+}
+
+#[test]
+fn failed_commits_put_the_old_install_back() {
+    let root = TempDir::new().unwrap();
+    let output_dir = root.path().join("version-1");
+    fs::create_dir_all(&output_dir).unwrap();
+    fs::write(output_dir.join("RobloxPlayerBeta.exe"), "old").unwrap();
+
+    // Nothing was staged, so moving it into place fails
+    let error = staging::commit(&staging::staging_dir(&output_dir), &output_dir).unwrap_err();
+
+    assert_eq!(error.kind(), "io", "{}", error);
+    assert_eq!(fs::read_to_string(output_dir.join("RobloxPlayerBeta.exe")).unwrap(), "old");
+}
+
+#[tokio::test]
+async fn leftovers_are_swept_on_the_next_start() {
+    let _guard = isolate().await;
+    let root = TempDir::new().unwrap();
+    let (kept, interrupted) = (root.path().join("version-1"), root.path().join("version-2"));
+    for output_dir in [&kept, &interrupted] {
+        fs::create_dir_all(staging::staging_dir(output_dir)).unwrap();
+        fs::create_dir_all(staging::previous_dir(output_dir)).unwrap();
+        fs::write(staging::previous_dir(output_dir).join("RobloxPlayerBeta.exe"), "old").unwrap();
+    }
+    // version-2 stopped between moving the old install aside and the new one in
+    fs::create_dir_all(&kept).unwrap();
+
+    let mut installs = registry::load().unwrap();
+    installs.staging.clear();
+    for output_dir in [&kept, &interrupted] {
+        installs.mark_staging(&staging::staging_dir(output_dir)).unwrap();
+        installs.mark_staging(&staging::previous_dir(output_dir)).unwrap();
+    }
+
+    assert_eq!(registry::cleanup_staging().unwrap(), 4);
+
+    let mut names: Vec<_> = fs::read_dir(root.path()).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    names.sort();
+    assert_eq!(names, ["version-1", "version-2"]);
+    assert_eq!(fs::read_to_string(interrupted.join("RobloxPlayerBeta.exe")).unwrap(), "old");
+    assert!(registry::load().unwrap().staging.is_empty());
+}
+
+#[tokio::test]
+async fn installs_that_are_still_running_are_not_swept() {
+    let _guard = isolate().await;
+    let root = TempDir::new().unwrap();
+    let output_dir = root.path().join("version-3");
+    let staging_dir = staging::staging_dir(&output_dir);
+    fs::create_dir_all(&staging_dir).unwrap();
+
+    let mut installs = registry::load().unwrap();
+    installs.staging.clear();
+    installs.mark_staging(&staging_dir).unwrap();
+
+    let lock = staging::lock(&output_dir).unwrap();
+    assert_eq!(staging::lock(&output_dir).err().unwrap().kind(), "validation");
+    assert_eq!(registry::cleanup_staging().unwrap(), 0);
+    assert!(staging_dir.exists());
+
+    drop(lock);
+    assert_eq!(registry::cleanup_staging().unwrap(), 1);
+    assert!(!staging_dir.exists());
+}
@@ -4,6 +4,7 @@ import { motion, AnimatePresence } from 'framer-motion';
 import { FiRefreshCw, FiDroplet, FiMonitor, FiDownload, FiCheck, FiUser, FiSettings, FiStar, FiCpu, FiInfo, FiHardDrive, FiZap } from 'react-icons/fi';
 import { invoke } from '@tauri-apps/api/tauri';
 import { showNotification } from './components/NotificationSystem';
+import { errorMessage } from './utils/errors';
 
 const colorPresets = [
   { name: 'Crimson', color: '#DC2626', gradient: 'linear-gradient(135deg, #DC2626, #EF4444)' },
@@ -23,6 +24,7 @@ const Settings = () => {
   const [selectedPreset, setSelectedPreset] = useState(null);
   const [username, setUsername] = useState('User');
   const [activeSection, setActiveSection] = useState('appearance');
+  const [appConfig, setAppConfig] = useState(null);
   const [systemInfo, setSystemInfo] = useState({
     os: 'Loading...',
     cpu: 'Loading...',
@@ -60,6 +62,25 @@ const Settings = () => {
     getSystemInfo();
   }, [primaryColor]);
 
+  useEffect(() => {
+    invoke('get_app_config')
+      .then(setAppConfig)
+      .catch(error => console.error('Error loading settings:', error));
+  }, []);
+
+  const handleRunModeToggle = async (e) => {
+    const updated = {
+      ...appConfig,
+      downloads: { ...appConfig.downloads, run_mode: e.target.checked ? 'auto' : 'ask' }
+    };
+    try {
+      await invoke('save_app_config', { appConfig: updated });
+      setAppConfig(updated);
+    } catch (error) {
+      showNotification('error', 'Settings Error', `Failed to save settings: ${errorMessage(error)}`);
+    }
+  };
+
   const handleColorChange = (e) => {
     setPrimaryColor(e.target.value);
     setSelectedPreset(null);
@@ -539,6 +560,47 @@ const Settings = () => {
                   )}
                 </AnimatePresence>
               </div>
+
+              <div style={{
+                background: 'rgba(255, 255, 255, 0.03)',
+                borderRadius: '20px',
+                padding: '32px',
+                marginTop: '24px',
+                border: `1px solid ${primaryColor}22`
+              }}>
+                <label style={{
+                  display: 'flex',
+                  alignItems: 'center',
+                  justifyContent: 'space-between',
+                  gap: '16px',
+                  cursor: appConfig ? 'pointer' : 'not-allowed'
+                }}>
+                  <div>
+                    <h3 style={{
+                      fontSize: '20px',
+                      fontWeight: '600',
+                      margin: '0 0 4px 0',
+                      color: theme.text
+                    }}>
+                      Run Downloads Automatically
+                    </h3>
+                    <p style={{
+                      fontSize: '14px',
+                      color: `${theme.text}60`,
+                      margin: 0
+                    }}>
+                      When off, downloaded apps show their claimed publisher and checksum and wait for you to confirm
+                    </p>
+                  </div>
+                  <input
+                    type="checkbox"
+                    checked={appConfig?.downloads?.run_mode === 'auto'}
+                    disabled={!appConfig}
+                    onChange={handleRunModeToggle}
+                    style={{ width: '20px', height: '20px', accentColor: primaryColor }}
+                  />
+                </label>
+              </div>
             </motion.div>
           )}
 
@@ -0,0 +1,148 @@
+import React, { useState, useEffect } from 'react';
+import { motion } from 'framer-motion';
+import { invoke } from '@tauri-apps/api/tauri';
+import { open, save } from '@tauri-apps/api/dialog';
+import { FiPlay, FiUpload, FiDownload, FiTrash2, FiSave } from 'react-icons/fi';
+import { showNotification } from './NotificationSystem';
+import { errorMessage } from '../utils/errors';
+import { confirmProfile } from '../utils/downloads';
+
+const PROFILE_FILTERS = [{ name: 'Install Profile', extensions: ['json'] }];
+
+// Named app lists on the Apps page. `selected` are the app ids ticked on the
+// cards, which "Save as Profile" turns into a new profile.
+const ProfilesBar = ({ selected, onSaved, onFinished }) => {
+  const [profiles, setProfiles] = useState([]);
+  const [running, setRunning] = useState(null);
+
+  const refresh = () => {
+    invoke('list_profiles')
+      .then(setProfiles)
+      .catch(error => console.error('Failed to load install profiles:', error));
+  };
+
+  useEffect(refresh, []);
+
+  const withErrors = (title) => async (action) => {
+    try {
+      await action();
+      refresh();
+    } catch (error) {
+      showNotification('error', title, errorMessage(error));
+    }
+  };
+
+  const saveSelection = () => withErrors('Save Failed')(async () => {
+    const name = window.prompt('Profile name', '');
+    if (!name) {
+      return;
+    }
+    const profile = await invoke('save_profile', { name, apps: [...selected] });
+    showNotification('success', 'Profile Saved', `${profile.name} installs ${profile.apps.length} apps`);
+    onSaved();
+  });
+
+  const importProfile = () => withErrors('Import Failed')(async () => {
+    const path = await open({ filters: PROFILE_FILTERS });
+    if (!path) {
+      return;
+    }
+    const profile = await invoke('import_profile', { path });
+    showNotification('success', 'Profile Imported', profile.name);
+  });
+
+  const exportProfile = (profile) => withErrors('Export Failed')(async () => {
+    const path = await save({ defaultPath: `${profile.name}.json`, filters: PROFILE_FILTERS });
+    if (path) {
+      await invoke('export_profile', { name: profile.name, path });
+      showNotification('success', 'Profile Exported', path);
+    }
+  });
+
+  const deleteProfile = (profile) => withErrors('Delete Failed')(async () => {
+    if (window.confirm(`Delete the ${profile.name} profile?`)) {
+      await invoke('delete_profile', { name: profile.name });
+    }
+  });
+
+  const runProfile = async (profile) => {
+    if (!await confirmProfile(profile)) {
+      return;
+    }
+    setRunning(profile.name);
+    showNotification('info', 'Profile Started', `Installing ${profile.apps.length} apps from ${profile.name}...`);
+    try {
+      const report = await invoke('run_profile', { name: profile.name });
+      const failed = report.steps.filter(step => step.outcome === 'failed');
+      const type = failed.length > 0 ? 'error' : report.steps.some(step => step.outcome === 'reboot_required') ? 'warning' : 'success';
+      const details = failed.map(step => step.message).join('\n');
+      showNotification(type, 'Profile Finished', details ? `${report.summary}\n${details}` : report.summary);
+    } catch (error) {
+      showNotification('error', 'Profile Failed', errorMessage(error));
+    } finally {
+      setRunning(null);
+      onFinished();
+    }
+  };
+
+  const buttonStyle = {
+    background: 'rgba(255, 255, 255, 0.08)',
+    border: '1px solid rgba(255, 255, 255, 0.15)',
+    borderRadius: '8px',
+    color: '#fff',
+    padding: '6px 10px',
+    fontSize: '12px',
+    cursor: 'pointer',
+    display: 'flex',
+    alignItems: 'center',
+    gap: '6px'
+  };
+
+  return (
+    <motion.div
+      initial={{ opacity: 0, y: 10 }}
+      animate={{ opacity: 1, y: 0 }}
+      style={{ display: 'flex', flexWrap: 'wrap', alignItems: 'center', gap: '10px', marginBottom: '24px', maxWidth: '1200px' }}
+    >
+      {profiles.map(profile => (
+        <div
+          key={profile.name}
+          title={profile.apps.join(', ')}
+          style={{
+            display: 'flex',
+            alignItems: 'center',
+            gap: '6px',
+            padding: '6px 6px 6px 12px',
+            borderRadius: '10px',
+            background: 'rgba(255, 255, 255, 0.04)',
+            border: '1px solid rgba(255, 255, 255, 0.1)',
+            color: '#fff',
+            fontSize: '13px'
+          }}
+        >
+          <span style={{ marginRight: '4px' }}>{profile.name} ({profile.apps.length})</span>
+          <button style={buttonStyle} disabled={running !== null} onClick={() => runProfile(profile)}>
+            <FiPlay size={12} /> {running === profile.name ? 'Installing...' : 'Run'}
+          </button>
+          <button style={buttonStyle} title="Export" onClick={() => exportProfile(profile)}>
+            <FiUpload size={12} />
+          </button>
+          <button style={buttonStyle} title="Delete" disabled={running !== null} onClick={() => deleteProfile(profile)}>
+            <FiTrash2 size={12} />
+          </button>
+        </div>
+      ))}
+
+      <button style={buttonStyle} onClick={importProfile}>
+        <FiDownload size={12} /> Import Profile
+      </button>
+      {selected.size > 0 && (
+        <button style={buttonStyle} onClick={saveSelection}>
+          <FiSave size={12} /> Save {selected.size} Selected as Profile
+        </button>
+      )}
+    </motion.div>
+  );
+};
+
+export default ProfilesBar;
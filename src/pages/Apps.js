@@ -1,51 +1,108 @@
-import React, { useState } from 'react';
+import React, { useState, useEffect } from 'react';
 import { useTheme } from '../ThemeContext';
 import { motion } from 'framer-motion';
 import { invoke } from '@tauri-apps/api/tauri';
 import { showNotification } from '../components/NotificationSystem';
+import ProfilesBar from '../components/ProfilesBar';
+import { errorMessage } from '../utils/errors';
+import { installDownload, runDownload } from '../utils/downloads';
 import { ring } from 'ldrs';
-import { FiDownload } from 'react-icons/fi';
+import { FiDownload, FiCheck } from 'react-icons/fi';
 
 // Register the ring component
 ring.register();
 
-const apps = [
-  { name: 'Spotify', icon: 'spotify.png', url: 'https://download.scdn.co/SpotifySetup.exe', filename: 'SpotifySetup.exe', isComplex: true, color: '#1DB954' },
-  { name: 'Steam', icon: 'steam.png', url: 'https://cdn.akamai.steamstatic.com/client/installer/SteamSetup.exe', filename: 'SteamSetup.exe', isComplex: true, color: '#1B2838' },
-  { name: 'Discord', icon: 'discord.png', url: 'https://discord.com/api/download?platform=win', filename: 'DiscordSetup.exe', isComplex: true, color: '#5865F2' },
-  { name: 'Brave', icon: 'brave.png', url: 'https://referrals.brave.com/latest/BraveBrowserSetup.exe', filename: 'BraveBrowserSetup.exe', isComplex: true, color: '#FB542B' },
-  { name: 'Faceit AC', icon: 'faceit.png', url: 'https://cdn.faceit.com/faceit/anticheat/FaceitAC_1.0.17.36.exe', filename: 'FACEITInstaller_64.exe', isComplex: true, color: '#FF5500' },
-  { name: 'VLC', icon: 'vlc.png', url: 'https://get.videolan.org/vlc/3.0.20/win64/vlc-3.0.20-win64.exe', filename: 'vlc.exe', isComplex: true, color: '#FF8800' },
-  { name: 'Malwarebytes', icon: 'malwarebytes.png', url: 'https://downloads.malwarebytes.com/file/mb4_offline', filename: 'mbsetup.exe', isComplex: true, color: '#0078D4' },
-  { name: 'WinRAR', icon: 'winrar.png', url: 'https://www.rarlab.com/rar/winrar-x64-624.exe', filename: 'winrar.exe', isComplex: true, color: '#DC2626' },
-  { name: 'Epic Games', icon: 'epic.png', url: 'https://launcher-public-service-prod06.ol.epicgames.com/launcher/api/installer/download/EpicGamesLauncherInstaller.msi', filename: 'epic_installer.msi', isComplex: true, color: '#313131' },
-  { name: 'Stremio', icon: 'stremio.png', url: 'https://www.stremio.com/StremioSetup.exe', filename: 'StremioSetup.exe', isComplex: true, color: '#7B2CBF' },
-  { name: 'TuxlerVPN', icon: 'tuxler.png', url: 'https://cdn.tuxlervpn.com/windows/TuxlerVPNSetup.exe', filename: 'TuxlerVPNSetup.exe', isComplex: true, color: '#FF8C00' },
-  { name: 'Logitech Manager', icon: 'logitech.png', url: 'https://raw.githubusercontent.com/DragosKissLove/tfy-electron2312/master/src/utils/LogitechManager.exe', filename: 'LogitechManager.exe', color: '#00B8FC' },
-  { name: 'Filter Keys', icon: 'filterkeys.png', url: 'https://raw.githubusercontent.com/DragosKissLove/tfy-electron2312/master/src/utils/FilterKeysSetter.exe', filename: 'FilterKeysSetter.exe', color: '#9B59B6' },
-  { name: 'Geek Utility', icon: 'geek.png', url: 'https://raw.githubusercontent.com/DragosKissLove/tfy-electron2312/master/src/utils/geek.exe', filename: 'geek.exe', color: '#C0C0C0' },
-  { name: 'Visual Studio Code', icon: 'visual.png', url: 'https://update.code.visualstudio.com/latest/win32-x64-user/stable', filename: 'VSCodeSetup.exe', isComplex: true, color: '#007ACC' }
-];
-
 const Apps = () => {
   const { theme, primaryColor } = useTheme();
+  const [apps, setApps] = useState([]);
+  const [statuses, setStatuses] = useState({});
   const [downloadingApps, setDownloadingApps] = useState(new Set());
+  // Ticked cards, for saving as an install profile
+  const [selectedApps, setSelectedApps] = useState(new Set());
+
+  useEffect(() => {
+    invoke('list_apps')
+      .then(setApps)
+      .catch(error => {
+        console.error('Failed to load app catalog:', error);
+        showNotification('error', 'Apps Unavailable', `Failed to load app catalog: ${errorMessage(error)}`);
+      });
+
+    refreshStatuses();
+  }, []);
+
+  // Only decorates the cards, so a failure just leaves them at "Ready to install"
+  const refreshStatuses = () => {
+    invoke('get_app_statuses')
+      .then(list => setStatuses(Object.fromEntries(list.map(status => [status.id, status]))))
+      .catch(error => console.error('Failed to detect installed apps:', error));
+  };
+
+  const toggleSelected = (event, appId) => {
+    event.stopPropagation();
+    setSelectedApps(prev => {
+      const newSet = new Set(prev);
+      if (!newSet.delete(appId)) {
+        newSet.add(appId);
+      }
+      return newSet;
+    });
+  };
+
+  // Apps whose catalog entry says how to install them without any dialogs
+  const canInstallSilently = (app) => Boolean(app.installer) || app.silent_args?.length > 0;
+
+  const describeStatus = (status) => {
+    if (!status?.installed) {
+      return 'Ready to install';
+    }
+    const version = status.installed.version ? ` v${status.installed.version}` : '';
+    return status.update_available ? `Installed${version} / Update available` : `Installed${version}`;
+  };
+
+  const actionLabel = (status) => {
+    if (!status?.installed) {
+      return 'Install';
+    }
+    return status.update_available ? 'Update' : 'Reinstall';
+  };
 
   const installApp = async (app) => {
     try {
-      setDownloadingApps(prev => new Set([...prev, app.name]));
+      setDownloadingApps(prev => new Set([...prev, app.id]));
       showNotification('info', 'Download Started', `Downloading ${app.name}...`);
       
-      const result = await invoke('download_app', { url: app.url, filename: app.filename });
-      
-      showNotification('success', 'Download Complete', result);
+      if (canInstallSilently(app)) {
+        const download = await invoke('download_app', { appId: app.id });
+        const result = await installDownload(app.id, download);
+        if (!result) {
+          return;
+        }
+        if (result.outcome === 'installed') {
+          showNotification('success', 'Installed', result.message);
+        } else if (result.outcome === 'reboot_required') {
+          showNotification('warning', 'Restart Required', result.message);
+        } else {
+          showNotification('error', 'Install Failed', result.message);
+        }
+        refreshStatuses();
+        return;
+      }
+
+      const download = await invoke('download_app', { appId: app.id });
+      showNotification('success', 'Download Complete', `${app.name} saved to ${download.path}`);
+
+      const result = await runDownload(download);
+      if (result) {
+        showNotification('success', 'Started', result);
+      }
     } catch (e) {
       console.error(`Error installing ${app.name}:`, e);
-      showNotification('error', 'Download Failed', `Failed to download ${app.name}: ${e}`);
+      showNotification('error', 'Download Failed', `Failed to download ${app.name}: ${errorMessage(e)}`);
     } finally {
       setDownloadingApps(prev => {
         const newSet = new Set(prev);
-        newSet.delete(app.name);
+        newSet.delete(app.id);
         return newSet;
       });
     }
@@ -80,6 +137,12 @@ const Apps = () => {
         Install Applications
       </motion.h2>
 
+      <ProfilesBar
+        selected={selectedApps}
+        onSaved={() => setSelectedApps(new Set())}
+        onFinished={refreshStatuses}
+      />
+
       {/* Compact Apps Grid */}
       <motion.div
         layout
@@ -91,12 +154,13 @@ const Apps = () => {
         }}
       >
         {apps.map((app, index) => {
-          const isDownloading = downloadingApps.has(app.name);
+          const isDownloading = downloadingApps.has(app.id);
+          const status = statuses[app.id];
           const appColor = app.color || primaryColor;
           
           return (
             <motion.div
-              key={app.name}
+              key={app.id}
               layout
               initial={{ opacity: 0, scale: 0.9 }}
               animate={{ opacity: 1, scale: 1 }}
@@ -153,6 +217,30 @@ const Apps = () => {
                 }}
               />
 
+              {/* Profile selection, only for apps a profile can install unattended */}
+              {canInstallSilently(app) && (
+                <div
+                  title="Select for a profile"
+                  onClick={(event) => toggleSelected(event, app.id)}
+                  style={{
+                    position: 'absolute',
+                    top: '10px',
+                    right: '10px',
+                    width: '18px',
+                    height: '18px',
+                    borderRadius: '5px',
+                    border: `1px solid ${appColor}88`,
+                    background: selectedApps.has(app.id) ? appColor : 'transparent',
+                    display: 'flex',
+                    alignItems: 'center',
+                    justifyContent: 'center',
+                    zIndex: 2
+                  }}
+                >
+                  {selectedApps.has(app.id) && <FiCheck size={12} color="#fff" />}
+                </div>
+              )}
+
               {/* App Icon and Info */}
               <div style={{
                 display: 'flex',
@@ -201,13 +289,13 @@ const Apps = () => {
                   </h3>
                   <p style={{
                     fontSize: '12px',
-                    color: 'rgba(255, 255, 255, 0.5)',
+                    color: status?.update_available ? appColor : 'rgba(255, 255, 255, 0.5)',
                     margin: 0,
                     overflow: 'hidden',
                     textOverflow: 'ellipsis',
                     whiteSpace: 'nowrap'
                   }}>
-                    Ready to install
+                    {describeStatus(status)}
                   </p>
                 </div>
               </div>
@@ -254,7 +342,7 @@ const Apps = () => {
                 ) : (
                   <>
                     <FiDownload size={14} />
-                    Install
+                    {actionLabel(status)}
                   </>
                 )}
               </motion.button>
@@ -4,6 +4,8 @@ import { useTheme } from '../ThemeContext';
 import { FiDownload, FiRefreshCw, FiClock, FiChevronDown, FiList } from 'react-icons/fi';
 import { invoke } from '@tauri-apps/api/tauri';
 import { showNotification } from '../components/NotificationSystem';
+import { errorMessage } from '../utils/errors';
+import { runDownload } from '../utils/downloads';
 import { ring } from 'ldrs';
 
 // Register the ring component
@@ -32,18 +34,23 @@ const Extra = () => {
     loadSavedVersions();
   }, []);
 
-  const handleClick = async (name, url) => {
+  const handleClick = async (name, appId) => {
     try {
       setActiveButton(name);
       setStatus('Downloading...');
       showNotification('info', 'Download Started', `Downloading ${name}...`);
       
-      const result = await invoke('download_to_desktop_and_run', { name, url });
-      setStatus(result || `${name} has been downloaded and started`);
-      showNotification('success', 'Download Complete', `${name} has been downloaded and launched successfully!`);
+      const download = await invoke('download_to_desktop', { appId });
+      setStatus(`${name} saved to ${download.path}`);
+      showNotification('success', 'Download Complete', `${name} has been downloaded`);
+
+      const result = await runDownload(download);
+      if (result) {
+        setStatus(result);
+      }
     } catch (error) {
-      setStatus(`❌ Error downloading ${name}: ${error}`);
-      showNotification('error', 'Download Failed', `Failed to download ${name}: ${error}`);
+      setStatus(`❌ Error downloading ${name}: ${errorMessage(error)}`);
+      showNotification('error', 'Download Failed', `Failed to download ${name}: ${errorMessage(error)}`);
     } finally {
       setActiveButton(null);
     }
@@ -70,8 +77,8 @@ const Extra = () => {
       setStatus(result || '✅ Roblox downgrade completed successfully!');
       showNotification('success', 'Roblox Downgrade Complete', 'Roblox has been successfully downgraded!');
     } catch (error) {
-      setStatus(`❌ Error: ${error}`);
-      showNotification('error', 'Downgrade Failed', `Roblox downgrade failed: ${error}`);
+      setStatus(`❌ Error: ${errorMessage(error)}`);
+      showNotification('error', 'Downgrade Failed', `Roblox downgrade failed: ${errorMessage(error)}`);
     } finally {
       setIsDownloading(false);
     }
@@ -375,7 +382,7 @@ const Extra = () => {
           <motion.button
             whileHover={{ scale: 1.02 }}
             whileTap={{ scale: 0.98 }}
-            onClick={() => handleClick('Swift', 'https://raw.githubusercontent.com/DragosKissLove/tfy-electron2312/master/src/utils/Swift.exe')}
+            onClick={() => handleClick('Swift', 'swift')}
             disabled={activeButton === 'Swift'}
             style={{
               padding: '12px 24px',
@@ -410,7 +417,7 @@ const Extra = () => {
           <motion.button
             whileHover={{ scale: 1.02 }}
             whileTap={{ scale: 0.98 }}
-            onClick={() => handleClick('Solara', 'https://raw.githubusercontent.com/DragosKissLove/tfy-electron2312/master/src/utils/BootstrapperNew.exe')}
+            onClick={() => handleClick('Solara', 'solara')}
             disabled={activeButton === 'Solara'}
             style={{
               padding: '12px 24px',
@@ -4,6 +4,8 @@ import { motion, AnimatePresence } from 'framer-motion';
 import { FiZap, FiTool, FiDownload, FiShield, FiTrash2, FiBox, FiEye, FiSearch, FiPrinter, FiBell, FiMonitor, FiWifi, FiBluetooth, FiLayers, FiPlay } from 'react-icons/fi';
 import { invoke } from '@tauri-apps/api/tauri';
 import { showNotification } from '../components/NotificationSystem';
+import { errorMessage } from '../utils/errors';
+import { runDownload } from '../utils/downloads';
 import { ring } from 'ldrs';
 
 // Register the ring component
@@ -15,7 +17,7 @@ const tools = [
   { name: 'Activate Windows', function: 'activate_windows', icon: FiBox },
   { name: 'TFY Optimizations', function: 'run_optimization', icon: FiZap, isComplex: true },
   { name: 'Clean Temp Files', function: 'clean_temp', icon: FiTrash2, isComplex: true },
-  { name: 'Atlas Tools', function: 'install_atlas_tools', icon: FiTool, isComplex: true }
+  { name: 'Atlas Tools', function: 'install_atlas_tools', command: 'download_atlas_tools', icon: FiTool, isComplex: true }
 ];
 
 const windowsFeatures = [
@@ -52,12 +54,20 @@ const Tools = () => {
       setStatus('Processing...');
       showNotification('info', 'Operation Started', `Running ${tools.find(t => t.function === funcName)?.name}...`);
       
-      const result = await invoke('run_function', { name: funcName, args: null });
+      const tool = tools.find(t => t.function === funcName);
+      let result;
+      if (tool?.command) {
+        // Tools that download something hand back a download record to confirm and run
+        const download = await invoke(tool.command);
+        result = await runDownload(download) || `${download.name} saved to ${download.path}`;
+      } else {
+        result = await invoke('run_function', { name: funcName, args: null });
+      }
       setStatus(result || 'Operation completed successfully!');
       showNotification('success', 'Operation Complete', result || 'Operation completed successfully!');
     } catch (error) {
-      setStatus(`Error: ${error}`);
-      showNotification('error', 'Operation Failed', `Error: ${error}`);
+      setStatus(`Error: ${errorMessage(error)}`);
+      showNotification('error', 'Operation Failed', `Error: ${errorMessage(error)}`);
     } finally {
       setActiveButton(null);
     }
@@ -90,8 +100,8 @@ const Tools = () => {
         ...prev,
         [featureKey]: !newState
       }));
-      setStatus(`Error toggling ${featureName}: ${error}`);
-      showNotification('error', 'Feature Toggle Failed', `Error toggling ${featureName}: ${error}`);
+      setStatus(`Error toggling ${featureName}: ${errorMessage(error)}`);
+      showNotification('error', 'Feature Toggle Failed', `Error toggling ${featureName}: ${errorMessage(error)}`);
     }
   };
 
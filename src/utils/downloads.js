@@ -0,0 +1,38 @@
+import { invoke } from '@tauri-apps/api/tauri';
+import { ask } from '@tauri-apps/api/dialog';
+
+// True when auto-run is switched on in Settings, otherwise asks first
+const confirmed = async (title, message) => {
+  const config = await invoke('get_app_config');
+  if (config.downloads?.run_mode === 'auto') {
+    return true;
+  }
+  return ask(message, { title, type: 'warning' });
+};
+
+// Takes a download record from download_app & co and starts it, asking first
+// unless auto-run is switched on in Settings. Resolves to the run message, or
+// null when the user said no.
+export const runDownload = async (download) => {
+  if (!await confirmed(`Run ${download.name}?`, `${download.path}\n\n${download.summary}\n\nRun it now?`)) {
+    return null;
+  }
+
+  return invoke('run_downloaded_file', { id: download.id });
+};
+
+// Like runDownload(), but runs the catalog app's installer unattended.
+// Resolves to the install result, or null when the user said no.
+export const installDownload = async (appId, download) => {
+  if (!await confirmed(`Install ${download.name}?`, `${download.path}\n\n${download.summary}\n\nInstall it now?`)) {
+    return null;
+  }
+
+  return invoke('install_download', { appId, id: download.id });
+};
+
+// Asked once before every app of a profile is downloaded and installed
+export const confirmProfile = (profile) => confirmed(
+  `Run ${profile.name}?`,
+  `Downloads and installs ${profile.apps.join(', ')}, one after the other.\n\nStart now?`
+);
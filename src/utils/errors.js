@@ -0,0 +1,2 @@
+// Commands reject with { kind, message, details }, anything else is shown as-is
+export const errorMessage = (error) => error?.message ?? String(error);